@@ -1,15 +1,434 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use strum::VariantNames;
 
+use crate::tt_api::*;
+use crate::tt_common::{AtomicFileState, ColorMap, FileState, ScaleMode, TTGradients};
 use crate::tt_file::TTFile;
 use crate::tt_gui_state::TTStateGUI;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
-struct Cli
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+pub struct Cli
 {
     /// Choose input data
     #[arg(value_name = "FILE")]
     input_file : Option<TTFile>,
+    /// Run without GUI
+    #[command(subcommand)]
+    command :    Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand
+{
+    /// Convert text coded thermogram sequences (txt/tcts) to ttcf
+    Convert
+    {
+        #[arg(value_name = "FILE", required = true)]
+        input_files :   Vec<TTFile>,
+        /// Precision of stored values [Lossy 22 Bit, f32, f64]
+        #[arg(short, long, default_value = "Lossy 22 Bit", value_parser = parse_variant::<TTCFPrecision>)]
+        precision :     TTCFPrecision,
+        /// Source file handling after successful conversion [Keep, Delete, Archive]
+        #[arg(short, long, default_value = "Keep", value_parser = parse_variant::<SourcePolicy>)]
+        source_policy : SourcePolicy,
+        /// Archive directory (relative to source file directory)
+        #[arg(long, default_value = DEFAULT_ARCHIVE_DIR)]
+        archive_dir :   PathBuf,
+    },
+    /// Print dimensions & frame count
+    Info
+    {
+        #[arg(value_name = "FILE", required = true)]
+        input_files : Vec<TTFile>,
+    },
+    /// Render view to image file (png, bmp, tiff, ...) or export its raw values (csv, txt, npy)
+    Render(RenderArgs),
+}
+
+#[derive(Args)]
+struct RenderArgs
+{
+    /// Input data
+    #[arg(value_name = "FILE")]
+    input_file :      TTFile,
+    /// Output image, or raw values if extension is .csv, .txt or .npy (see --stack for image stacks)
+    #[arg(value_name = "OUTPUT")]
+    output :          PathBuf,
+    /// View domain [Time View, Fourier View, Fast Wavelet View, Wavelet View, Composite Wavelet View, PCT View, TSR View, Lock In View]
+    #[arg(short, long, default_value = "Time View", value_parser = parse_variant::<ViewModeDomain>)]
+    domain :          ViewModeDomain,
+    /// View axes (eg. X-Y, X-t, t-Y, X-s, s-Y, t-s, X-f, f-Y, X-c, c-Y, t-c)
+    #[arg(short, long, default_value = "X-Y", value_parser = parse_axes)]
+    axes :            [TTAxis; 2],
+    #[arg(short, long, default_value_t = 0)]
+    x :               usize,
+    #[arg(short, long, default_value_t = 0)]
+    y :               usize,
+    #[arg(short = 't', long, default_value_t = 0)]
+    frame :           usize,
+    /// Wavelet scale (1-based)
+    #[arg(short, long, default_value_t = 1)]
+    scale :           usize,
+    /// Fourier frequency bin
+    #[arg(short, long, default_value_t = 0)]
+    freq :            usize,
+    /// Principal component (1-based)
+    #[arg(long, default_value_t = 1)]
+    component :       usize,
+    #[arg(short, long, default_value = "Morlet", value_parser = parse_variant::<WaveletType>)]
+    wavelet :         WaveletType,
+    /// Complex result display mode [Phase, Magnitude, Real, Imaginary]
+    #[arg(short, long, default_value = "Phase", value_parser = parse_variant::<ComplexResultMode>)]
+    mode :            ComplexResultMode,
+    /// Composite view mode [Magnitude Sum, Max Magnitude Scale, Max Energy Phase]
+    #[arg(short, long, default_value = "Magnitude Sum", value_parser = parse_variant::<CompositeMode>)]
+    composite :       CompositeMode,
+    /// TSR view content [Reconstruction, First Derivative, Second Derivative]
+    #[arg(long, default_value = "Reconstruction", value_parser = parse_variant::<TSRMode>)]
+    tsr_mode :        TSRMode,
+    /// Degree of polynomial fitted in TSR view
+    #[arg(long, default_value_t = TSR_DEFAULT_DEGREE)]
+    tsr_degree :      usize,
+    /// Lock-in excitation frequency [Hz]
+    #[arg(long, default_value_t = 1.0)]
+    lockin_freq :     f64,
+    /// Lock-in demodulation method [Correlation, Four Point]
+    #[arg(long, default_value = "Correlation", value_parser = parse_variant::<LockInMethod>)]
+    lockin_method :   LockInMethod,
+    /// Acquisition frame rate [Hz] [default: from file metadata, otherwise 50]
+    #[arg(long)]
+    frame_rate :      Option<f64>,
+    /// Scales combined in composite view (1-based, inclusive)
+    #[arg(long, default_value = "1-16", value_parser = parse_range)]
+    scales :          [usize; 2],
+    /// Differential time view (subtract reference frame)
+    #[arg(long)]
+    diff :            bool,
+    /// Subtract reference frame before Fourier & wavelet processing
+    #[arg(long)]
+    diff_preprocess : bool,
+    #[arg(short, long, default_value_t = 0)]
+    reference_frame : usize,
+    /// Fourier apodisation window (eg. Rectangular, Hann, Kaiser 2.5, Opt Gap Hann, Kaiser 17 Opt)
+    #[arg(long, default_value = "Kaiser 17 Opt", value_parser = parse_variant::<WindowType>)]
+    window :          WindowType,
+    /// Export views at all positions along given axis (eg. f) as image stack (output: .tif, .tiff or numbered .png)
+    #[arg(long, value_parser = parse_axis)]
+    stack :           Option<TTAxis>,
+    /// Color map of non-phase views [Inferno, Grey, Ironbow, Rainbow, Viridis, Turbo, RdBu, Custom]
+    #[arg(long, default_value = "Inferno", value_parser = parse_variant::<ColorMap>)]
+    color_map :       ColorMap,
+    /// Reverse color map
+    #[arg(long)]
+    reverse_colors :  bool,
+    /// Gradient of Custom color map: text file with one color (eg. #ff8000 or 255 128 0) per line
+    #[arg(long)]
+    gradient :        Option<PathBuf>,
+}
+
+//=======================================
+//=============CLI helpers===============
+//=======================================
+
+///case, space & underscore insensitive parsing of enum variant names
+fn parse_variant<T>(s : &str) -> Result<T, String>
+where T : VariantNames + FromStr
+{
+    let normalize = |s : &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    T::VARIANTS
+        .iter()
+        .find(|&&var| normalize(var) == normalize(s))
+        .and_then(|&var| T::from_str(var).ok())
+        .ok_or_else(|| format!("possible values: {}", T::VARIANTS.join(", ")))
+}
+fn parse_axes(s : &str) -> Result<[TTAxis; 2], String>
+{
+    let axes : Vec<_> = s
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(TTAxis::try_from)
+        .collect();
+    match axes[..]
+    {
+        [Ok(a), Ok(b)] => Ok([a, b]),
+        _ => Err("expected two of X, Y, t, s, f (eg. X-Y)".to_string()),
+    }
+}
+fn parse_axis(s : &str) -> Result<TTAxis, String>
+{
+    let mut chars = s.trim().chars();
+    match (chars.next().map(TTAxis::try_from), chars.next())
+    {
+        (Some(Ok(axis)), None) => Ok(axis),
+        _ => Err("expected one of X, Y, t, s, f, c".to_string()),
+    }
+}
+fn parse_range(s : &str) -> Result<[usize; 2], String>
+{
+    let (min, max) = s
+        .split_once('-')
+        .ok_or_else(|| "expected MIN-MAX".to_string())?;
+    let min = min.trim().parse::<usize>().map_err(|e| e.to_string())?;
+    let max = max.trim().parse::<usize>().map_err(|e| e.to_string())?;
+    if min < 1 || max < min
+    {
+        return Err("expected 1 <= MIN <= MAX".to_string());
+    }
+    Ok([min, max])
+}
+fn cli_load(file : &mut TTFile) -> Option<TTInputData>
+{
+    match file.data_load(
+        Arc::new(AtomicFileState::new(FileState::Loading)),
+        &Default::default(),
+        &Default::default(),
+    )
+    {
+        Ok(data) => Some(data),
+        Err(error) =>
+        {
+            eprintln!("{}: invalid file: {}", file.path(), error);
+            None
+        }
+    }
+}
+
+impl Cli
+{
+    ///runs requested command without GUI
+    ///returns `None` if no command was requested (GUI should be started), otherwise process exit code
+    pub fn run_batch(&self) -> Option<i32>
+    {
+        let success = match self.command.as_ref()?
+        {
+            CliCommand::Convert {
+                input_files,
+                precision,
+                source_policy,
+                archive_dir,
+            } =>
+            {
+                let params = TTStoreParams {
+                    precision :     *precision,
+                    source_policy : *source_policy,
+                    archive_dir :   archive_dir.clone(),
+                };
+                input_files.iter().fold(true, |success, file| {
+                    let mut file = file.clone();
+                    let converted = match cli_load(&mut file)
+                    {
+                        Some(_) if file.is_ttcf() =>
+                        {
+                            //`data_store()` does not rewrite ttcf files
+                            println!("{}: skipped: already ttcf", file.path());
+                            return success;
+                        }
+                        Some(data) => file.data_store(&data, &params),
+                        None => return false,
+                    };
+                    match converted
+                    {
+                        Ok(_) => println!("{}: converted", file.path()),
+                        Err(ref e) => eprintln!("{}: conversion failed: {}", file.path(), e),
+                    }
+                    success & converted.is_ok()
+                })
+            }
+            CliCommand::Info { input_files } =>
+            {
+                input_files.iter().fold(true, |success, file| {
+                    let mut file = file.clone();
+                    match cli_load(&mut file)
+                    {
+                        Some(data) =>
+                        {
+                            println!(
+                                "{}: {}x{} px, {} frames ({} with padding)",
+                                file.path(),
+                                data.width,
+                                data.height,
+                                data.frames,
+                                data.data.dim().2
+                            );
+                            if let Some(frame_rate) = data.metadata.frame_rate
+                            {
+                                println!("  frame rate [Hz]: {}", frame_rate);
+                            }
+                            if let (Some(name), Some(hash)) =
+                                (&data.metadata.source_name, data.metadata.source_hash)
+                            {
+                                println!("  converted from: {} (hash {:016x})", name, hash);
+                            }
+                            for (key, value) in data.metadata.entries.iter()
+                            {
+                                println!("  {}: {}", key, value);
+                            }
+                            success
+                        }
+                        None => false,
+                    }
+                })
+            }
+            CliCommand::Render(args) => args.run(),
+        };
+        Some(if success { 0 } else { 1 })
+    }
+}
+impl RenderArgs
+{
+    fn run(&self) -> bool
+    {
+        if let Some(path) = &self.gradient
+        {
+            if let Err(e) = TTGradients::load_custom(path)
+            {
+                eprintln!("{}: {}", path.to_string_lossy(), e);
+                return false;
+            }
+        }
+        let mut file = self.input_file.clone();
+        let input = match cli_load(&mut file)
+        {
+            Some(input) => input,
+            None => return false,
+        };
+        let processing_params = TTProcessingParams {
+            diff_reference : if self.diff_preprocess
+            {
+                Some(self.reference_frame)
+            }
+            else
+            {
+                None
+            },
+            window :         self.window,
+        };
+        let frame_rate = self
+            .frame_rate
+            .or(input.metadata.frame_rate)
+            .unwrap_or(DEFAULT_FRAME_RATE);
+        let mut processor = TTProcessor::new(input, processing_params);
+        let view_params = TTViewParams {
+            domain :          self.domain,
+            view_axes :       self.axes,
+            position :        [
+                self.x,
+                self.y,
+                self.frame,
+                self.scale.saturating_sub(1),
+                self.freq,
+                self.component.saturating_sub(1),
+            ],
+            wavelet :         self.wavelet,
+            display_mode :    self.mode,
+            composite_mode :  self.composite,
+            scale_band :      [self.scales[0] - 1, self.scales[1] - 1],
+            tsr_mode :        self.tsr_mode,
+            tsr_degree :      self.tsr_degree,
+            lockin_freq :     self.lockin_freq,
+            lockin_method :   self.lockin_method,
+            frame_rate :      frame_rate,
+            differential :    self.diff,
+            reference_frame : self.reference_frame,
+            roi :             None,
+        };
+        if let Some(axis) = self.stack
+        {
+            return match processor.export_stack(&view_params, axis, &self.output)
+            {
+                Ok(_) =>
+                {
+                    println!("{}: saved", self.output.to_string_lossy());
+                    true
+                }
+                Err(e) =>
+                {
+                    eprintln!("{}: {}", self.output.to_string_lossy(), e);
+                    false
+                }
+            };
+        }
+        let view = match processor.raw_view(&view_params)
+        {
+            Some(view) => view,
+            None =>
+            {
+                eprintln!(
+                    "requested axes are not available in this domain, position is out of range or \
+                     frequency is not positive"
+                );
+                return false;
+            }
+        };
+        let raw_output = matches!(
+            self.output
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .as_deref(),
+            Some("csv" | "txt" | "npy")
+        );
+        if raw_output
+        {
+            return match view.export(&self.output)
+            {
+                Ok(_) =>
+                {
+                    println!("{}: saved", self.output.to_string_lossy());
+                    true
+                }
+                Err(e) =>
+                {
+                    eprintln!("{}: {}", self.output.to_string_lossy(), e);
+                    false
+                }
+            };
+        }
+        let view = view.data;
+        let linear = TTGradients::Linear(self.color_map, self.reverse_colors);
+        let grad = match self.domain
+        {
+            ViewModeDomain::TimeView | ViewModeDomain::PCTView | ViewModeDomain::TSRView => linear,
+            ViewModeDomain::CompositeWaveletView
+                if self.composite == CompositeMode::MaxEnergyPhase =>
+            {
+                TTGradients::Phase
+            }
+            ViewModeDomain::CompositeWaveletView => linear,
+            _ if self.mode == ComplexResultMode::Phase => TTGradients::Phase,
+            _ => linear,
+        };
+        let scale = grad.scale(
+            view.iter().cloned().collect(),
+            ScaleMode::Equalized,
+            Default::default(),
+            Default::default(),
+        );
+        let rgb = grad.colorize(view.view(), &scale);
+        let (width, height) = view.dim();
+        let image = image::RgbImage::from_raw(width as u32, height as u32, rgb).unwrap(); //rgb buffer always matches view size
+        match image.save(&self.output)
+        {
+            Ok(_) =>
+            {
+                println!("{}: saved", self.output.to_string_lossy());
+                true
+            }
+            Err(e) =>
+            {
+                eprintln!("{}: {}", self.output.to_string_lossy(), e);
+                false
+            }
+        }
+    }
 }
 
 pub struct ThermoTransformApp
@@ -20,9 +439,8 @@ pub struct ThermoTransformApp
 impl ThermoTransformApp
 {
     /// Called once before the first frame.
-    pub fn new(cc : &eframe::CreationContext<'_>) -> Self
+    pub fn new(cc : &eframe::CreationContext<'_>, cli : Cli) -> Self
     {
-        let cli = Cli::parse();
         // This is also where you can customized the look at feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
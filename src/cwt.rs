@@ -4,12 +4,12 @@ use ndarray::Array3;
 use ndarray::ArrayView1;
 use ndarray::ArrayViewMut3;
 use ndarray::Zip;
+use num_complex::Complex64;
 use rayon::prelude::IndexedParallelIterator;
 use rayon::prelude::IntoParallelIterator;
 use rayon::prelude::ParallelIterator;
 use rayon::slice::ParallelSliceMut;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
 
 use crate::tt_common::*;
 use crate::tt_fourier::*;
@@ -21,27 +21,51 @@ pub struct TTLazyCWT
 }
 impl TTLazyCWT
 {
-    pub fn new(fourier : &TTFourier, file_state : Arc<AtomicFileState>) -> Option<TTLazyCWT>
+    ///returns `None` if processing was cancelled
+    pub fn new(fourier : &TTFourier, cancel : &TTCancelToken) -> Option<TTLazyCWT>
     {
-        let integrated = TTLazyCWT {
-            integrals : fourier.integrals(),
-        };
-
-        if FileState::ProcessingWavelet == file_state.load(Ordering::Relaxed)
-        {
-            Some(integrated)
-        }
-        else
-        {
-            None
-        }
+        Some(TTLazyCWT {
+            integrals : fourier.integrals(cancel)?,
+        })
+    }
+    ///same as `new()`, but cannot be cancelled
+    pub fn from_fourier(fourier : &TTFourier) -> TTLazyCWT
+    {
+        Self::new(fourier, &Default::default()).unwrap() //default token is never cancelled
     }
+    ///1st to 4th integral of input signal along time axis (X, Y, T)
+    pub fn integrals(&self) -> &[Array3<f64>; 4] { &self.integrals }
+    ///returns `None` if processing was cancelled
     pub fn cwt(
         &self,
         wavelet_bank : &mut WaveletBank,
         params : &ViewMode,
         settings : &GlobalSettings,
-    ) -> Array2<f64>
+        cancel : &TTCancelToken,
+    ) -> Option<Array2<f64>>
+    {
+        let display_mode = params.display_mode.load(Ordering::Relaxed);
+        //convert to requested format
+        self.cwt_map(wavelet_bank, params, settings, cancel, |re, im| {
+            match display_mode
+            {
+                ComplexResultMode::Phase => im.atan2(re), //radians
+                ComplexResultMode::Magnitude => re.hypot(im),
+                ComplexResultMode::Real => re,
+                ComplexResultMode::Imaginary => im,
+            }
+        })
+    }
+    ///wavelet transform with (real, imaginary) part of each value converted by `convert`;
+    ///returns `None` if processing was cancelled
+    fn cwt_map<T : Clone + Default + Send + Sync>(
+        &self,
+        wavelet_bank : &mut WaveletBank,
+        params : &ViewMode,
+        settings : &GlobalSettings,
+        cancel : &TTCancelToken,
+        convert : impl Fn(f64, f64) -> T + Sync,
+    ) -> Option<Array2<T>>
     {
         let wavelet = wavelet_bank
             .get_mut(&params.wavelet.load(Ordering::Relaxed))
@@ -153,14 +177,7 @@ impl TTLazyCWT
                     accum
                 })
                 .collect();
-            //convert to requested format
-            match params.display_mode.load(Ordering::Relaxed)
-            {
-                ComplexResultMode::Phase => real_img[1].atan2(real_img[0]), //radians
-                ComplexResultMode::Magnitude => real_img[0].hypot(real_img[1]),
-                ComplexResultMode::Real => real_img[0],
-                ComplexResultMode::Imaginary => real_img[1],
-            }
+            convert(real_img[0], real_img[1])
         };
         let view_axes = params.get_view_axes();
         let mut size = [0, 0];
@@ -244,7 +261,7 @@ impl TTLazyCWT
         };
         let avm3 = (x_range.len(), y_range.len(), 1);
         let slice_arg = s![x_range, y_range, ..];
-        let mut v : Vec<f64> = vec![0.0; size[0] * size[1]];
+        let mut v : Vec<T> = vec![T::default(); size[0] * size[1]];
         s_range
             .into_par_iter()
             .zip(v.par_chunks_exact_mut(size[0] * size[1] / s_chunk_div))
@@ -255,6 +272,10 @@ impl TTLazyCWT
                     .into_par_iter()
                     .zip(v.par_chunks_exact_mut(v.len() / t_chunk_div))
                     .for_each(|(t, v)| {
+                        if cancel.is_cancelled()
+                        {
+                            return;
+                        }
                         Zip::from(self.integrals[0].slice(slice_arg).lanes(AXIS_T))
                             .and(self.integrals[1].slice(slice_arg).lanes(AXIS_T))
                             .and(self.integrals[2].slice(slice_arg).lanes(AXIS_T))
@@ -269,8 +290,12 @@ impl TTLazyCWT
                             })
                     })
             });
+        if cancel.is_cancelled()
+        {
+            return None;
+        }
         //if X-t, X-s, t-s transpose
-        match view_axes
+        let view = match view_axes
         {
             [TTAxis::X, TTAxis::T] | [TTAxis::X, TTAxis::S] | [TTAxis::T, TTAxis::S] =>
             {
@@ -286,18 +311,123 @@ impl TTLazyCWT
                     .reversed_axes() //counter transposition in TTViewBackend::update_image()
             }
             _ => unreachable!(),
+        };
+        Some(view)
+    }
+}
+
+impl TTLazyCWT
+{
+    ///combines wavelet transforms over `params.scale_band` into single image (see `CompositeMode`)
+    ///returns `None` if processing was cancelled
+    pub fn composite(
+        &self,
+        wavelet_bank : &mut WaveletBank,
+        params : &ViewMode,
+        settings : &GlobalSettings,
+        cancel : &TTCancelToken,
+    ) -> Option<Array2<f64>>
+    {
+        //ViewMode for single scale wavelet transform with same view axes as composite view
+        let scale_params = params.clone();
+        let view_axes = params.get_view_axes();
+        let wavelet_mode_counter = ViewMode::ViewModeAxes[ViewModeDomain::FastWaveletView as usize]
+            .iter()
+            .position(|(axes, _)| *axes == view_axes)
+            .unwrap(); //all composite view axes are also available in wavelet view
+        scale_params
+            .domain
+            .store(ViewModeDomain::FastWaveletView, Ordering::Relaxed);
+        scale_params
+            .mode_counter
+            .store(wavelet_mode_counter, Ordering::Relaxed);
+        scale_params
+            .display_mode
+            .store(ComplexResultMode::Magnitude, Ordering::Relaxed);
+
+        let [s_min, s_max] = *params.scale_band.read();
+        let s_max = s_max.min(settings.full_size.read()[TTAxis::S as usize]);
+        let s_min = s_min.min(s_max);
+        let composite_mode = params.composite_mode.load(Ordering::Relaxed);
+
+        let mut max_magnitude : Option<Array2<f64>> = None;
+        let mut composite : Option<Array2<f64>> = None;
+        for s in s_min..=s_max
+        {
+            scale_params.position.write()[TTAxis::S as usize] = s;
+            let (magnitude, phase) = if composite_mode == CompositeMode::MaxEnergyPhase
+            {
+                //magnitude & phase of single complex transform
+                let cwt = self.cwt_map(
+                    wavelet_bank,
+                    &scale_params,
+                    settings,
+                    cancel,
+                    Complex64::new,
+                )?;
+                (cwt.map(|x| x.norm()), Some(cwt.map(|x| x.arg())))
+            }
+            else
+            {
+                (
+                    self.cwt(wavelet_bank, &scale_params, settings, cancel)?,
+                    None,
+                )
+            };
+            match composite_mode
+            {
+                CompositeMode::MagnitudeSum =>
+                {
+                    match composite
+                    {
+                        Some(ref mut sum) => *sum += &magnitude,
+                        None => composite = Some(magnitude),
+                    }
+                }
+                CompositeMode::MaxMagnitudeScale | CompositeMode::MaxEnergyPhase =>
+                {
+                    //phase or scale (scales are displayed 1-based) of max magnitude
+                    let value =
+                        phase.unwrap_or_else(|| Array2::from_elem(magnitude.dim(), (s + 1) as f64));
+                    match (&mut max_magnitude, &mut composite)
+                    {
+                        (Some(max_magnitude), Some(composite)) =>
+                        {
+                            Zip::from(max_magnitude)
+                                .and(composite)
+                                .and(&magnitude)
+                                .and(&value)
+                                .par_for_each(|max, c, &m, &v| {
+                                    if m > *max
+                                    {
+                                        *max = m;
+                                        *c = v;
+                                    }
+                                });
+                        }
+                        _ =>
+                        {
+                            max_magnitude = Some(magnitude);
+                            composite = Some(value);
+                        }
+                    }
+                }
+            }
         }
+        composite //scale band contains at least one scale
     }
 }
 
 impl TTInputData
 {
+    ///returns `None` if processing was cancelled
     pub fn cwt(
         &self,
         wavelet_bank : &mut WaveletBank,
         params : &ViewMode,
         settings : &GlobalSettings,
-    ) -> Array2<f64>
+        cancel : &TTCancelToken,
+    ) -> Option<Array2<f64>>
     {
         let wavelet = wavelet_bank
             .get_mut(&params.wavelet.load(Ordering::Relaxed))
@@ -395,6 +525,10 @@ impl TTInputData
                     .into_par_iter()
                     .zip(v.par_chunks_exact_mut(v.len() / t_chunk_div))
                     .for_each(|(t, v)| {
+                        if cancel.is_cancelled()
+                        {
+                            return;
+                        }
                         Zip::from(self.data.slice(slice_arg).lanes(AXIS_T))
                             .and(
                                 ArrayViewMut3::from_shape(avm3, v)
@@ -402,6 +536,10 @@ impl TTInputData
                                     .lanes_mut(AXIS_T),
                             )
                             .par_for_each(|data, mut v| {
+                                if cancel.is_cancelled()
+                                {
+                                    return;
+                                }
                                 let real_img : Vec<f64> = polywise
                                     .0
                                     .iter()
@@ -474,8 +612,12 @@ impl TTInputData
                             })
                     })
             });
+        if cancel.is_cancelled()
+        {
+            return None;
+        }
         //if X-t, X-s, t-s transpose
-        match view_axes
+        let view = match view_axes
         {
             [TTAxis::X, TTAxis::T] | [TTAxis::X, TTAxis::S] | [TTAxis::T, TTAxis::S] =>
             {
@@ -491,6 +633,7 @@ impl TTInputData
                     .reversed_axes() //counter transposition in TTViewBackend::update_image()
             }
             _ => unreachable!(),
-        }
+        };
+        Some(view)
     }
 }
@@ -1,7 +1,37 @@
+use atomic_enum::atomic_enum;
 use ndarray::Array1;
+use strum_macros::{EnumString, EnumVariantNames};
+
 #[derive(Debug, Clone, Copy)]
 pub struct GAPWin([f64; 11]);
 
+///apodisation window applied along time axis before Fourier transform
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum WindowType
+{
+    ///no window
+    Rectangular,
+    Tukey,
+    Bohman,
+    Gauss,
+    Nuttall,
+    Blackman_Harris,
+    Hamming,
+    Hann,
+    Blackman,
+    Flattop,
+    #[strum(serialize = "Kaiser 2.5")]
+    Kaiser_2p5,
+    Opt_Gap_Flattop,
+    Opt_Gap_Hann,
+    Opt_Gap_Nuttall,
+    Opt_Gap_Tukey,
+    #[default]
+    Kaiser_17_Opt,
+}
+
 impl GAPWin
 {
     pub fn window(&self, length : usize) -> Array1<f64>
@@ -69,10 +99,38 @@ impl GAPWin
     }
 }
 
+impl WindowType
+{
+    pub fn gap_win(&self) -> GAPWin
+    {
+        match self
+        {
+            WindowType::Rectangular => GAPWin::RECTANGULAR,
+            WindowType::Tukey => GAPWin::TUKEY,
+            WindowType::Bohman => GAPWin::BOHMAN,
+            WindowType::Gauss => GAPWin::GAUSS,
+            WindowType::Nuttall => GAPWin::NUTTALL,
+            WindowType::Blackman_Harris => GAPWin::BLACKMANHARRIS,
+            WindowType::Hamming => GAPWin::HAMMING,
+            WindowType::Hann => GAPWin::HANN,
+            WindowType::Blackman => GAPWin::BLACKMAN,
+            WindowType::Flattop => GAPWin::FLATTOP,
+            WindowType::Kaiser_2p5 => GAPWin::KAISER_2P5,
+            WindowType::Opt_Gap_Flattop => GAPWin::OPT_GAP_FLATTOP,
+            WindowType::Opt_Gap_Hann => GAPWin::OPT_GAP_HANN,
+            WindowType::Opt_Gap_Nuttall => GAPWin::OPT_GAP_NUTTALL,
+            WindowType::Opt_Gap_Tukey => GAPWin::OPT_GAP_TUKEY,
+            WindowType::Kaiser_17_Opt => GAPWin::KAISER_17_OPT,
+        }
+    }
+}
+
 //consts
 #[allow(dead_code)]
 impl GAPWin
 {
+    ///constant (no windowing)
+    pub const RECTANGULAR : Self = Self([1., 0., 0., 0., 0., 0., 0., 0., 0., 0., 0.]);
     ///Cosine fraction equal to 0.5
     pub const TUKEY : Self = Self([
         1.,
@@ -1,13 +1,20 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![allow(nonstandard_style)]
 mod app;
-pub use app::ThermoTransformApp;
+pub use app::{Cli, ThermoTransformApp};
 mod cwt;
 mod gap_window;
+pub mod tt_api;
 mod tt_backend_state;
 mod tt_common;
+mod tt_export;
 mod tt_file;
+mod tt_flir;
 mod tt_fourier;
 mod tt_gui_state;
+mod tt_lockin;
+mod tt_pct;
+mod tt_plot;
+mod tt_tsr;
 mod wavelet;
 pub use macros;
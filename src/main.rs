@@ -1,6 +1,7 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![allow(nonstandard_style)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+use clap::Parser;
 use eframe::IconData;
 use image;
 
@@ -10,6 +11,11 @@ fn main()
 {
     // Log to stdout (if you run with `RUST_LOG=debug`).
     tracing_subscriber::fmt::init();
+    let cli = ThermoTransform::Cli::parse();
+    if let Some(exit_code) = cli.run_batch()
+    {
+        std::process::exit(exit_code);
+    }
     let icon_raw = include_bytes!("../icon.ico");
     let icon = image::load_from_memory_with_format(icon_raw.as_slice(), image::ImageFormat::Ico)
         .unwrap()
@@ -27,7 +33,7 @@ fn main()
     let _ = eframe::run_native(
         "ThermoTransform",
         native_options,
-        Box::new(|cc| Box::new(ThermoTransform::ThermoTransformApp::new(cc))),
+        Box::new(|cc| Box::new(ThermoTransform::ThermoTransformApp::new(cc, cli))),
     );
 }
 
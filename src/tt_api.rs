@@ -0,0 +1,501 @@
+//! GUI independent processing API
+//!
+//! ```no_run
+//! use ThermoTransform::tt_api::*;
+//! let input = TTProcessor::load("sequence.ttcf").unwrap();
+//! let mut processor = TTProcessor::new(input, TTProcessingParams::default());
+//! let params = TTViewParams {
+//!     domain : ViewModeDomain::FourierView,
+//!     view_axes : [TTAxis::X, TTAxis::Y],
+//!     position : [0, 0, 0, 0, 1, 0],
+//!     ..Default::default()
+//! };
+//! let phase = processor.view(&params).unwrap();
+//! processor.raw_view(&params).unwrap().export("phase.npy".as_ref()).unwrap();
+//! ```
+use ndarray::{Array2, Array3, Dimension, IntoDimension};
+use num_complex::Complex64;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
+pub use crate::cwt::TTLazyCWT;
+pub use crate::gap_window::WindowType;
+use crate::tt_common::{AtomicFileState, FileState, GlobalSettings, ViewMode};
+pub use crate::tt_common::{
+    ComplexResultMode,
+    CompositeMode,
+    LockInMethod,
+    SourcePolicy,
+    TSRMode,
+    TTAxis,
+    TTCFPrecision,
+    TTInputData,
+    TTMetadata,
+    TTProcessingParams,
+    TTRawView,
+    TTStoreParams,
+    ViewModeDomain,
+    DEFAULT_ARCHIVE_DIR,
+    DEFAULT_FRAME_RATE,
+};
+pub use crate::tt_export::DecimalSeparator;
+pub use crate::tt_file::{TTFile, TTLoadError};
+pub use crate::tt_fourier::TTFourier;
+pub use crate::tt_pct::TTPct;
+pub use crate::tt_tsr::{TTTsr, TSR_DEFAULT_DEGREE};
+pub use crate::wavelet::WaveletType;
+use crate::wavelet::{WaveletBank, WaveletBankTrait};
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///plain counterpart of `ViewMode` & view related `GlobalSettings`
+#[derive(Clone, PartialEq)]
+pub struct TTViewParams
+{
+    pub domain :          ViewModeDomain,
+    ///horizontal & vertical axis of requested view
+    pub view_axes :       [TTAxis; 2],
+    ///position along axes, that are not in `view_axes` (indexed with `TTAxis as usize`)
+    pub position :        [usize; 6],
+    pub wavelet :         WaveletType,
+    pub display_mode :    ComplexResultMode,
+    pub composite_mode :  CompositeMode,
+    ///range of scales(inclusive) combined in composite view
+    pub scale_band :      [usize; 2],
+    pub tsr_mode :        TSRMode,
+    ///degree of polynomial fitted in TSR view
+    pub tsr_degree :      usize,
+    ///excitation frequency [Hz] demodulated in lock-in view
+    pub lockin_freq :     f64,
+    pub lockin_method :   LockInMethod,
+    ///acquisition frame rate [Hz]
+    pub frame_rate :      f64,
+    ///time view only: subtract `reference_frame`
+    pub differential :    bool,
+    pub reference_frame : usize,
+    ///if set, view is limited to region [min; max) along each axis (indexed with `TTAxis as usize`)
+    pub roi :             Option<([usize; 6], [usize; 6])>,
+}
+
+///processing pipeline for single input sequence; transforms are calculated lazily on first use
+pub struct TTProcessor
+{
+    input :        TTInputData,
+    params :       TTProcessingParams,
+    fourier :      Option<TTFourier>,
+    lazy_cwt :     Option<TTLazyCWT>,
+    pct :          Option<TTPct>,
+    tsr :          Option<TTTsr>,
+    wavelet_bank : WaveletBank,
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl Default for TTViewParams
+{
+    fn default() -> Self
+    {
+        Self {
+            domain :          ViewModeDomain::TimeView,
+            view_axes :       [TTAxis::X, TTAxis::Y],
+            position :        [0; 6],
+            wavelet :         Default::default(),
+            display_mode :    Default::default(),
+            composite_mode :  Default::default(),
+            scale_band :      [0, 15],
+            tsr_mode :        Default::default(),
+            tsr_degree :      TSR_DEFAULT_DEGREE,
+            lockin_freq :     1.0,
+            lockin_method :   Default::default(),
+            frame_rate :      DEFAULT_FRAME_RATE,
+            differential :    false,
+            reference_frame : 0,
+            roi :             None,
+        }
+    }
+}
+
+impl TTViewParams
+{
+    ///returns `None` if `view_axes` are not available in `domain`, `position` or `roi` is out of
+    ///range, frame rate is not positive or lock-in frequency is not positive in `LockInView`
+    fn view_mode(&self, input : &TTInputData) -> Option<(ViewMode, GlobalSettings)>
+    {
+        let full_size = input.full_size();
+        if self
+            .position
+            .iter()
+            .zip(full_size.slice())
+            .any(|(pos, max)| pos > max)
+            || self.reference_frame > full_size[TTAxis::T as usize]
+            || !(self.frame_rate > 0.0)
+            || (matches!(self.domain, ViewModeDomain::LockInView) && !(self.lockin_freq > 0.0))
+        {
+            return None;
+        }
+        if let Some((roi_min, roi_max)) = self.roi
+        {
+            //roi is [min; max), so max can be 1 past last index
+            if (0..6)
+                .any(|axis| roi_min[axis] > roi_max[axis] || roi_max[axis] > full_size[axis] + 1)
+            {
+                return None;
+            }
+        }
+        let mode_counter = ViewMode::ViewModeAxes[self.domain as usize]
+            .iter()
+            .position(|(axes, _)| *axes == self.view_axes)?;
+        let view_mode = ViewMode::new(self.domain, self.wavelet, self.display_mode);
+        view_mode.mode_counter.store(mode_counter, Relaxed);
+        *view_mode.position.write() = self.position.into_dimension();
+        view_mode.differential.store(self.differential, Relaxed);
+        view_mode.composite_mode.store(self.composite_mode, Relaxed);
+        *view_mode.scale_band.write() = self.scale_band;
+        view_mode.tsr_mode.store(self.tsr_mode, Relaxed);
+        view_mode.tsr_degree.store(self.tsr_degree, Relaxed);
+        *view_mode.lockin_freq.write() = self.lockin_freq;
+        view_mode.lockin_method.store(self.lockin_method, Relaxed);
+
+        let settings = GlobalSettings::default();
+        *settings.full_size.write() = full_size;
+        settings
+            .reference_frame
+            .store(self.reference_frame, Relaxed);
+        *settings.frame_rate.write() = self.frame_rate;
+        settings.fft_len.store(input.data.dim().2, Relaxed);
+        if let Some((roi_min, roi_max)) = self.roi
+        {
+            *settings.roi_min.write() = roi_min.into_dimension();
+            *settings.roi_max.write() = roi_max.into_dimension();
+            settings.roi_zoom.store(true, Relaxed);
+        }
+        Some((view_mode, settings))
+    }
+}
+
+impl TTProcessor
+{
+    pub fn load(path : impl Into<PathBuf>) -> Result<TTInputData, TTLoadError>
+    {
+        let mut file = TTFile::from(path.into().into_os_string());
+        file.data_load(
+            Arc::new(AtomicFileState::new(FileState::Loading)),
+            &Default::default(),
+            &Default::default(),
+        )
+    }
+    pub fn new(input : TTInputData, params : TTProcessingParams) -> Self
+    {
+        Self {
+            input,
+            params,
+            fourier : None,
+            lazy_cwt : None,
+            pct : None,
+            tsr : None,
+            wavelet_bank : WaveletBank::new_wb(),
+        }
+    }
+    pub fn input(&self) -> &TTInputData { &self.input }
+    pub fn params(&self) -> &TTProcessingParams { &self.params }
+    ///changing processing params invalidates already calculated transforms
+    pub fn set_params(&mut self, params : TTProcessingParams)
+    {
+        if self.params != params
+        {
+            self.params = params;
+            self.fourier = None;
+            self.lazy_cwt = None;
+        }
+    }
+    pub fn fourier(&mut self) -> &TTFourier
+    {
+        self.fourier
+            .get_or_insert_with(|| TTFourier::transform(&self.input, &self.params))
+    }
+    ///Fourier transform along time axis (X, Y, F)
+    pub fn spectrum(&mut self) -> &Array3<Complex64> { self.fourier().data() }
+    pub fn lazy_cwt(&mut self) -> &TTLazyCWT
+    {
+        self.prepare_lazy_cwt();
+        self.lazy_cwt.as_ref().unwrap()
+    }
+    ///principal component thermography (independent of processing params)
+    pub fn pct(&mut self) -> &TTPct
+    {
+        let input = &self.input;
+        self.pct.get_or_insert_with(|| TTPct::new(input))
+    }
+    ///thermographic signal reconstruction with polynomial of given `degree` (refitted if degree or params changed)
+    pub fn tsr(&mut self, degree : usize) -> &TTTsr
+    {
+        if self
+            .tsr
+            .as_ref()
+            .map_or(true, |tsr| !tsr.matches(&self.params, degree))
+        {
+            self.tsr = Some(TTTsr::new(&self.input, &self.params, degree));
+        }
+        self.tsr.as_ref().unwrap()
+    }
+    fn prepare_lazy_cwt(&mut self)
+    {
+        if self.lazy_cwt.is_none()
+        {
+            let lazy_cwt = TTLazyCWT::from_fourier(self.fourier());
+            self.lazy_cwt = Some(lazy_cwt);
+        }
+    }
+    ///calculates requested 2D view (without any color mapping/normalization), indexed [view_axes[0], view_axes[1]]
+    ///returns `None` if `params.view_axes` are not available in `params.domain` or position is out of range
+    pub fn view(&mut self, params : &TTViewParams) -> Option<Array2<f64>>
+    {
+        let (view_mode, settings) = params.view_mode(&self.input)?;
+        let view = match params.domain
+        {
+            ViewModeDomain::TimeView => self.input.time_view(&view_mode, &settings),
+            ViewModeDomain::FourierView => self.fourier().snapshot(&view_mode, &settings),
+            ViewModeDomain::FastWaveletView =>
+            {
+                self.prepare_lazy_cwt();
+                self.lazy_cwt.as_ref().unwrap().cwt(
+                    &mut self.wavelet_bank,
+                    &view_mode,
+                    &settings,
+                    &Default::default(),
+                )?
+            }
+            ViewModeDomain::WaveletView =>
+            {
+                self.input.cwt(
+                    &mut self.wavelet_bank,
+                    &view_mode,
+                    &settings,
+                    &Default::default(),
+                )?
+            }
+            ViewModeDomain::CompositeWaveletView =>
+            {
+                self.prepare_lazy_cwt();
+                self.lazy_cwt.as_ref().unwrap().composite(
+                    &mut self.wavelet_bank,
+                    &view_mode,
+                    &settings,
+                    &Default::default(),
+                )?
+            }
+            ViewModeDomain::PCTView => self.pct().snapshot(&view_mode, &settings),
+            ViewModeDomain::LockInView => self.input.lock_in(&view_mode, &settings),
+            ViewModeDomain::TSRView => self.tsr(params.tsr_degree).snapshot(&view_mode, &settings),
+        };
+        if params.view_axes[0] > params.view_axes[1]
+        {
+            //views are calculated with axes in `TTAxis` order
+            Some(view.reversed_axes())
+        }
+        else
+        {
+            Some(view)
+        }
+    }
+    ///same as `view()`, but with axis labels (for export)
+    pub fn raw_view(&mut self, params : &TTViewParams) -> Option<TTRawView>
+    {
+        let data = self.view(params)?;
+        let offset = match params.roi
+        {
+            Some((roi_min, _)) => params.view_axes.map(|axis| roi_min[axis as usize]),
+            None => [0, 0],
+        };
+        Some(TTRawView {
+            data,
+            axes : params.view_axes,
+            offset,
+        })
+    }
+    ///views at every position along `axis` (eg. phase at all frequencies), written as image stack
+    ///(see `TTRawView::export_stack()`); position of `axis` in `params` is ignored
+    pub fn export_stack(
+        &mut self,
+        params : &TTViewParams,
+        axis : TTAxis,
+        path : &Path,
+    ) -> io::Result<()>
+    {
+        let invalid = |msg : &str| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let settings_axes = ViewMode::ViewModeAxes[params.domain as usize]
+            .iter()
+            .find(|(axes, _)| *axes == params.view_axes)
+            .ok_or_else(|| invalid("requested axes are not available in this domain"))?
+            .1;
+        if !settings_axes.contains(&axis)
+        {
+            return Err(invalid(
+                "stack axis has to be one of settings axes of view mode",
+            ));
+        }
+        let mut params = params.clone();
+        let last = self.input.full_size()[axis as usize];
+        let mut views = Vec::with_capacity(last + 1);
+        for position in 0..=last
+        {
+            params.position[axis as usize] = position;
+            let view = self.raw_view(&params).ok_or_else(|| {
+                invalid(
+                    "requested axes are not available in this domain or params are out of range",
+                )
+            })?;
+            views.push(view);
+        }
+        TTRawView::export_stack(&views, path)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const WIDTH : usize = 6;
+    const HEIGHT : usize = 5;
+    const FRAMES : usize = 16;
+
+    fn synthetic_input() -> TTInputData
+    {
+        TTInputData {
+            frames :   FRAMES,
+            width :    WIDTH,
+            height :   HEIGHT,
+            data :     Array3::from_shape_fn((WIDTH, HEIGHT, FRAMES), |(x, y, t)| {
+                ((x + 2 * y) as f64 * 0.3 + t as f64 * 0.7).sin() + t as f64 * 0.1
+            }),
+            metadata : Default::default(),
+        }
+    }
+    fn processor() -> TTProcessor { TTProcessor::new(synthetic_input(), Default::default()) }
+
+    #[test]
+    fn xy_view_dimensions_in_every_domain()
+    {
+        let mut processor = processor();
+        for domain in [
+            ViewModeDomain::TimeView,
+            ViewModeDomain::FourierView,
+            ViewModeDomain::FastWaveletView,
+            ViewModeDomain::WaveletView,
+            ViewModeDomain::CompositeWaveletView,
+            ViewModeDomain::PCTView,
+            ViewModeDomain::TSRView,
+            ViewModeDomain::LockInView,
+        ]
+        {
+            let params = TTViewParams {
+                domain,
+                position : [0, 0, 3, 2, 1, 0],
+                frame_rate : 8.0,
+                ..Default::default()
+            };
+            let view = processor.view(&params).unwrap();
+            assert_eq!(view.dim(), (WIDTH, HEIGHT), "{}", domain.as_ref());
+        }
+    }
+
+    #[test]
+    fn view_axes_order_and_roi()
+    {
+        let mut processor = processor();
+        let params = TTViewParams {
+            view_axes : [TTAxis::T, TTAxis::Y],
+            ..Default::default()
+        };
+        assert_eq!(processor.view(&params).unwrap().dim(), (FRAMES, HEIGHT));
+        let params = TTViewParams {
+            roi : Some(([1, 1, 0, 0, 0, 0], [4, HEIGHT, FRAMES, 0, 0, 0])),
+            ..Default::default()
+        };
+        assert_eq!(processor.view(&params).unwrap().dim(), (3, HEIGHT - 1));
+    }
+
+    #[test]
+    fn out_of_range_params_are_rejected()
+    {
+        let mut processor = processor();
+        let rejected = [
+            TTViewParams {
+                position : [WIDTH, 0, 0, 0, 0, 0],
+                view_axes : [TTAxis::T, TTAxis::Y],
+                ..Default::default()
+            },
+            TTViewParams {
+                position : [0, 0, FRAMES, 0, 0, 0],
+                ..Default::default()
+            },
+            TTViewParams {
+                reference_frame : FRAMES,
+                differential : true,
+                ..Default::default()
+            },
+            TTViewParams {
+                roi : Some(([3, 0, 0, 0, 0, 0], [2, HEIGHT, FRAMES, 0, 0, 0])),
+                ..Default::default()
+            },
+            TTViewParams {
+                roi : Some(([0, 0, 0, 0, 0, 0], [WIDTH + 2, HEIGHT, FRAMES, 0, 0, 0])),
+                ..Default::default()
+            },
+            TTViewParams {
+                domain : ViewModeDomain::LockInView,
+                view_axes : [TTAxis::X, TTAxis::T],
+                ..Default::default()
+            },
+            TTViewParams {
+                frame_rate : 0.0,
+                ..Default::default()
+            },
+            TTViewParams {
+                domain : ViewModeDomain::LockInView,
+                lockin_freq : 0.0,
+                ..Default::default()
+            },
+        ];
+        for params in rejected
+        {
+            assert!(processor.view(&params).is_none());
+        }
+        //lock-in frequency is used only by lock-in view
+        let params = TTViewParams {
+            lockin_freq : 0.0,
+            ..Default::default()
+        };
+        assert!(processor.view(&params).is_some());
+    }
+
+    #[test]
+    fn stack_axis_has_to_be_settings_axis()
+    {
+        let mut processor = processor();
+        let path = std::env::temp_dir().join("tt_api_stack_test.tiff");
+        //X-Y view of time domain is set only by T; S axis would export repeated views
+        for axis in [TTAxis::X, TTAxis::S, TTAxis::C]
+        {
+            let error = processor
+                .export_stack(&Default::default(), axis, &path)
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        let params = TTViewParams {
+            view_axes : [TTAxis::X, TTAxis::F],
+            ..Default::default()
+        };
+        let error = processor
+            .export_stack(&params, TTAxis::Y, &path)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -1,17 +1,20 @@
 use crate::cwt::*;
 use crate::tt_common::*;
-use crate::tt_file::TTFile;
+use crate::tt_file::{TTFile, TTLoadError};
 use crate::tt_fourier::TTFourier;
-use crate::wavelet::{WaveletBank, WaveletBankTrait};
+use crate::tt_pct::TTPct;
+use crate::tt_plot::TTPixelPlots;
+use crate::tt_tsr::TTTsr;
+use crate::wavelet::{AtomicWaveletType, WaveletBank, WaveletBankTrait};
 use egui::{ColorImage, TextureOptions};
 use ndarray::Axis;
 use ndarray::{s, ArrayView2, IntoDimension};
 use ndarray_ndimage::{convolve, BorderMode};
-use parking_lot::{Condvar, Mutex};
+use parking_lot::{Condvar, Mutex, RwLock};
 use rayon::prelude::ParallelIterator;
 use rayon::prelude::{IntoParallelIterator, ParallelExtend};
-use rayon::slice::ParallelSliceMut;
 use std::f64::consts::{FRAC_1_PI, PI};
+use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use triple_buffer as tribuf;
@@ -29,6 +32,14 @@ pub struct TTViewBackend
     pub settings :         Arc<GlobalSettings>,
     pub frozen_settings :  GlobalSettings,
 }
+pub struct TTPlotBackend
+{
+    pub state :    Arc<AtomicTTViewState>,
+    pub plots :    tribuf::Input<TTPixelPlots>,
+    ///wavelet of plotted wavelet transform
+    pub wavelet :  Arc<AtomicWaveletType>,
+    pub settings : Arc<GlobalSettings>,
+}
 
 #[derive(Default)]
 struct TTFileBackendData
@@ -36,24 +47,59 @@ struct TTFileBackendData
     input_data : Option<TTInputData>,
     lazy_cwt :   Option<TTLazyCWT>,
     fourier :    Option<TTFourier>,
+    ///calculated on first request
+    pct :        Option<TTPct>,
+    ///calculated on first request (& recalculated when fit params change)
+    tsr :        Option<TTTsr>,
 }
 struct TTFileBackend
 {
-    state : Arc<AtomicFileState>,
-    path :  tribuf::Output<Option<TTFile>>,
-    data :  TTFileBackendData,
+    state :  Arc<AtomicFileState>,
+    path :   tribuf::Output<Option<TTFile>>,
+    info :   Arc<RwLock<TTFileInfo>>,
+    data :   TTFileBackendData,
+    ///loaded file has been converted to ttcf (reprocessing does not convert it again)
+    stored : bool,
+}
+///file information written by backend & displayed by gui
+#[derive(Default)]
+pub struct TTFileInfo
+{
+    pub metadata :    TTMetadata,
+    ///error of last loading attempt
+    pub load_error :  Option<TTLoadError>,
+    ///error of last conversion to ttcf
+    pub store_error : Option<String>,
+    ///progress of ongoing loading
+    pub progress :    Arc<TTProgress>,
 }
 
 pub struct TTStateBackend
 {
     views :        [TTViewBackend; 4],
+    plot :         TTPlotBackend,
     changed :      Arc<(Mutex<bool>, Condvar)>,
     stop_flag :    Arc<AtomicBool>,
     file :         TTFileBackend,
     wavelet_bank : WaveletBank,
     settings :     Arc<GlobalSettings>,
+    ///cancels view calculations when loaded data is dropped or app is closing
+    view_cancel :  TTCancelToken,
 }
 
+//=======================================
+//================Consts=================
+//=======================================
+///file states in which loaded input data stays valid
+const VIEW_STAGES : &[FileState] = &[
+    FileState::Loaded,
+    FileState::Reprocessing,
+    FileState::ProcessingFourier,
+    FileState::ProcessingWavelet,
+    FileState::ReadySaving,
+    FileState::Ready,
+];
+
 //=======================================
 //============Implementations============
 //=======================================
@@ -77,24 +123,9 @@ impl TTViewBackend
             {
                 if let Some(input) = input_data
                 {
-                    let settings_axis = self.frozen_view_mode.get_settings_axes()[0] as usize;
-                    let input_view = input.data.index_axis(
-                        Axis(settings_axis),
-                        self.frozen_view_mode.position.read()[settings_axis],
-                    );
-                    let view = if self.frozen_settings.roi_zoom.load(Relaxed)
-                    {
-                        let view_axes = self.frozen_view_mode.get_view_axes();
-                        let roi_h = self.frozen_settings.get_roi(view_axes[0]);
-                        let roi_v = self.frozen_settings.get_roi(view_axes[1]);
-                        input_view.slice(s![roi_h, roi_v])
-                    }
-                    else
-                    {
-                        input_view
-                    };
+                    let view = input.time_view(&self.frozen_view_mode, &self.frozen_settings);
                     let denoise = self.frozen_view_mode.denoise.load(Relaxed);
-                    self.update_image(view, TTGradients::Linear, denoise);
+                    self.update_image(view.view(), self.frozen_view_mode.gradient(), denoise);
                 }
             }
         }
@@ -103,6 +134,7 @@ impl TTViewBackend
         &mut self,
         input_data : &Option<TTInputData>,
         wavelet_bank : &mut WaveletBank,
+        cancel : &TTCancelToken,
     ) -> ()
     {
         if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::WaveletView
@@ -119,9 +151,23 @@ impl TTViewBackend
                     //// calculate & display requested waveletet transform
                     // let mut exec_time = ExecutionTimeMeas::new("exec_time_cwt.txt");
                     // exec_time.start();
-                    let cwt_view =
-                        input.cwt(wavelet_bank, &self.frozen_view_mode, &self.frozen_settings);
+                    let cwt_view = input.cwt(
+                        wavelet_bank,
+                        &self.frozen_view_mode,
+                        &self.frozen_settings,
+                        cancel,
+                    );
                     // exec_time.stop_print("cwt: ");
+                    let cwt_view = match cwt_view
+                    {
+                        Some(cwt_view) => cwt_view,
+                        None =>
+                        {
+                            //cancelled, view is recalculated later
+                            self.state.store(TTViewState::Changed, Relaxed);
+                            return;
+                        }
+                    };
                     let denoise = self.frozen_view_mode.denoise.load(Relaxed);
                     if self.frozen_view_mode.display_mode.load(Relaxed) == ComplexResultMode::Phase
                     {
@@ -129,7 +175,11 @@ impl TTViewBackend
                     }
                     else
                     {
-                        self.update_image(cwt_view.view(), TTGradients::Linear, denoise);
+                        self.update_image(
+                            cwt_view.view(),
+                            self.frozen_view_mode.gradient(),
+                            denoise,
+                        );
                     };
                     // exec_time.stop_print("update_image: ");
                 }
@@ -140,6 +190,7 @@ impl TTViewBackend
         &mut self,
         lazy_cwt : &Option<TTLazyCWT>,
         wavelet_bank : &mut WaveletBank,
+        cancel : &TTCancelToken,
     ) -> ()
     {
         if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::FastWaveletView
@@ -156,9 +207,23 @@ impl TTViewBackend
                     //// calculate & display requested waveletet transform
                     // let mut exec_time = ExecutionTimeMeas::new("exec_time_cwt.txt");
                     // exec_time.start();
-                    let cwt_view =
-                        cwt.cwt(wavelet_bank, &self.frozen_view_mode, &self.frozen_settings);
+                    let cwt_view = cwt.cwt(
+                        wavelet_bank,
+                        &self.frozen_view_mode,
+                        &self.frozen_settings,
+                        cancel,
+                    );
                     // exec_time.stop_print("cwt: ");
+                    let cwt_view = match cwt_view
+                    {
+                        Some(cwt_view) => cwt_view,
+                        None =>
+                        {
+                            //cancelled, view is recalculated later
+                            self.state.store(TTViewState::Changed, Relaxed);
+                            return;
+                        }
+                    };
                     let denoise = self.frozen_view_mode.denoise.load(Relaxed);
                     if self.frozen_view_mode.display_mode.load(Relaxed) == ComplexResultMode::Phase
                     {
@@ -166,13 +231,159 @@ impl TTViewBackend
                     }
                     else
                     {
-                        self.update_image(cwt_view.view(), TTGradients::Linear, denoise);
+                        self.update_image(
+                            cwt_view.view(),
+                            self.frozen_view_mode.gradient(),
+                            denoise,
+                        );
                     };
                     // exec_time.stop_print("update_image: ");
                 }
             }
         }
     }
+    fn composite_view_check_update(
+        &mut self,
+        lazy_cwt : &Option<TTLazyCWT>,
+        wavelet_bank : &mut WaveletBank,
+        cancel : &TTCancelToken,
+    ) -> ()
+    {
+        if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::CompositeWaveletView
+        {
+            if let Ok(_) = self.state.compare_exchange(
+                TTViewState::Changed,
+                TTViewState::Processing,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            )
+            {
+                if let Some(cwt) = lazy_cwt
+                {
+                    let composite_view = match cwt.composite(
+                        wavelet_bank,
+                        &self.frozen_view_mode,
+                        &self.frozen_settings,
+                        cancel,
+                    )
+                    {
+                        Some(composite_view) => composite_view,
+                        None =>
+                        {
+                            //cancelled, view is recalculated later
+                            self.state.store(TTViewState::Changed, Relaxed);
+                            return;
+                        }
+                    };
+                    let denoise = self.frozen_view_mode.denoise.load(Relaxed);
+                    if self.frozen_view_mode.composite_mode.load(Relaxed)
+                        == CompositeMode::MaxEnergyPhase
+                    {
+                        self.update_image(composite_view.view(), TTGradients::Phase, denoise);
+                    }
+                    else
+                    {
+                        self.update_image(
+                            composite_view.view(),
+                            self.frozen_view_mode.gradient(),
+                            denoise,
+                        );
+                    };
+                }
+            }
+        }
+    }
+    fn pct_view_check_update(
+        &mut self,
+        input_data : &Option<TTInputData>,
+        pct : &mut Option<TTPct>,
+    ) -> ()
+    {
+        if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::PCTView
+        {
+            if let Ok(_) = self.state.compare_exchange(
+                TTViewState::Changed,
+                TTViewState::Processing,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            )
+            {
+                if let Some(input) = input_data
+                {
+                    let pct = pct.get_or_insert_with(|| TTPct::new(input));
+                    let snapshot = pct.snapshot(&self.frozen_view_mode, &self.frozen_settings);
+                    let denoise = self.frozen_view_mode.denoise.load(Relaxed);
+                    self.update_image(snapshot.view(), self.frozen_view_mode.gradient(), denoise);
+                }
+            }
+        }
+    }
+    fn tsr_view_check_update(
+        &mut self,
+        input_data : &Option<TTInputData>,
+        tsr : &mut Option<TTTsr>,
+    ) -> ()
+    {
+        if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::TSRView
+        {
+            if let Ok(_) = self.state.compare_exchange(
+                TTViewState::Changed,
+                TTViewState::Processing,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            )
+            {
+                if let Some(input) = input_data
+                {
+                    let params = self.frozen_settings.processing_params();
+                    let degree = self.frozen_view_mode.tsr_degree.load(Relaxed);
+                    if tsr
+                        .as_ref()
+                        .map_or(true, |tsr| !tsr.matches(&params, degree))
+                    {
+                        *tsr = Some(TTTsr::new(input, &params, degree));
+                    }
+                    let snapshot = tsr
+                        .as_ref()
+                        .unwrap()
+                        .snapshot(&self.frozen_view_mode, &self.frozen_settings);
+                    let denoise = self.frozen_view_mode.denoise.load(Relaxed);
+                    self.update_image(snapshot.view(), self.frozen_view_mode.gradient(), denoise);
+                }
+            }
+        }
+    }
+    fn lock_in_view_check_update(&mut self, input_data : &Option<TTInputData>) -> ()
+    {
+        if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::LockInView
+        {
+            if let Ok(_) = self.state.compare_exchange(
+                TTViewState::Changed,
+                TTViewState::Processing,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            )
+            {
+                if let Some(input) = input_data
+                {
+                    let lock_in = input.lock_in(&self.frozen_view_mode, &self.frozen_settings);
+                    let denoise = self.frozen_view_mode.denoise.load(Relaxed);
+                    if self.frozen_view_mode.display_mode.load(Relaxed) == ComplexResultMode::Phase
+                    {
+                        self.update_image(lock_in.view(), TTGradients::Phase, denoise);
+                    }
+                    else
+                    {
+                        self.update_image(
+                            lock_in.view(),
+                            self.frozen_view_mode.gradient(),
+                            denoise,
+                        );
+                    };
+                }
+            }
+        }
+    }
     fn fourier_view_check_update(&mut self, fourier : &Option<TTFourier>) -> ()
     {
         if self.frozen_view_mode.domain.load(Relaxed) == ViewModeDomain::FourierView
@@ -194,7 +405,11 @@ impl TTViewBackend
                     }
                     else
                     {
-                        self.update_image(snapshot.view(), TTGradients::Linear, denoise);
+                        self.update_image(
+                            snapshot.view(),
+                            self.frozen_view_mode.gradient(),
+                            denoise,
+                        );
                     };
                 }
             }
@@ -208,9 +423,23 @@ impl TTViewBackend
     ) -> ()
     {
         let view_axes = self.frozen_view_mode.get_view_axes();
+        let raw = TTRawView::new(
+            if view_axes[0] > view_axes[1]
+            {
+                array.t().to_owned()
+            }
+            else
+            {
+                array.to_owned()
+            },
+            &self.frozen_view_mode,
+            &self.frozen_settings,
+        );
         let mut array = array.into_owned();
+        let scale_mode = self.frozen_view_mode.scale_mode.load(Relaxed);
         if (view_axes[0] == TTAxis::X) ^ (view_axes[1] == TTAxis::Y)
-        //view modes X-t, X-s, t-Y, s-Y
+            && scale_mode == ScaleMode::Equalized
+        //view modes X-t, X-s, t-Y, s-Y (other scale modes show absolute values)
         {
             //use contrast/normalized values insted of absolute ones
             let slice_idxs = match (self.frozen_settings.roi_zoom.load(Relaxed), view_axes[0])
@@ -274,7 +503,6 @@ impl TTViewBackend
             array = convolve(&array, &filter, BorderMode::Reflect, 0);
         }
 
-        let array_iter = array.t().into_iter();
         let image_dim = [array.dim().0, array.dim().1];
 
         let mut array_vec;
@@ -302,65 +530,26 @@ impl TTViewBackend
             array_vec.par_extend(array.into_par_iter());
         };
 
-        if grad == TTGradients::Phase
+        let range = if scale_mode == ScaleMode::Linked
         {
-            //for phase gradient force -PI & PI as extreme vals
-            array_vec.push(-PI);
-            array_vec.push(PI);
+            *self.frozen_settings.linked_range.read()
         }
-        array_vec
-            .as_parallel_slice_mut()
-            .sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        let quantile_count = (array_vec.len() - 1) as f64 / 32.0;
+        else
+        {
+            *self.frozen_view_mode.scale_range.read()
+        };
+        let clip_percent = *self.frozen_view_mode.clip_percent.read();
+        let scale = grad.scale(array_vec, scale_mode, range, clip_percent);
+        let rgb = grad.colorize(array.view(), &scale);
         let gram = self.thermogram.input_buffer();
-        gram.scale.iter_mut().enumerate().for_each(|(idx, val)| {
-            *val = array_vec[(quantile_count * idx as f64).round() as usize]
-        });
-        let quantile_out_width = 1.0 / 33.0;
-        let mul_add_coef : Vec<(_, _)> = gram
-            .scale
-            .windows(2)
-            .zip((0..32).map(|x| {
-                (
-                    x as f64 * quantile_out_width,
-                    (x + 1) as f64 * quantile_out_width,
-                )
-            }))
-            .map(|(x, (ymin, ymax))| {
-                let (xmin, xmax) = (x[0], x[1]);
-                let mul = (ymax - ymin) / (xmax - xmin);
-                let add = ymin - xmin * mul;
-                (mul, add)
-            })
-            .collect();
-        let rgb = array_iter
-            // .into_par_iter()
-            .map(|&x| {
-                let color;
-                match gram
-                    .scale
-                    .binary_search_by(|a| a.partial_cmp(&x).unwrap_or(std::cmp::Ordering::Equal))
-                {
-                    Ok(idx) =>
-                    {
-                        color = grad
-                            .raw_grad()
-                            .at(quantile_out_width * idx as f64)
-                            .to_rgba8();
-                    }
-                    Err(idx) =>
-                    {
-                        //idx will always be in range <1;32>
-                        let (mul, add) = mul_add_coef[idx - 1];
-                        color = grad.raw_grad().at(f64::mul_add(x, mul, add)).to_rgba8();
-                    }
-                };
-                [color[0], color[1], color[2]]
-            })
-            .flatten()
-            .collect::<Vec<u8>>();
+        gram.scale = scale;
         let color_image = ColorImage::from_rgb(image_dim, &rgb);
+        gram.pixels = color_image.clone();
         gram.image.set(color_image, TextureOptions::LINEAR);
+        gram.range = raw.range();
+        gram.raw = raw;
+        gram.view_mode = self.frozen_view_mode.clone();
+        gram.settings = self.frozen_settings.clone();
         if grad == TTGradients::Phase
         {
             //for phase scale values to degrees
@@ -380,31 +569,102 @@ impl TTViewBackend
 }
 impl TTFileBackend
 {
-    pub fn new(state : Arc<AtomicFileState>, path : tribuf::Output<Option<TTFile>>) -> Self
+    pub fn new(
+        state : Arc<AtomicFileState>,
+        path : tribuf::Output<Option<TTFile>>,
+        info : Arc<RwLock<TTFileInfo>>,
+    ) -> Self
     {
         Self {
             state,
             path,
+            info,
             data : Default::default(),
+            stored : false,
         }
     }
 }
+impl TTPlotBackend
+{
+    fn check_update(
+        &mut self,
+        input_data : &Option<TTInputData>,
+        fourier : &Option<TTFourier>,
+        lazy_cwt : &Option<TTLazyCWT>,
+        wavelet_bank : &mut WaveletBank,
+        cancel : &TTCancelToken,
+    ) -> ()
+    {
+        if let Ok(_) = self.state.compare_exchange(
+            TTViewState::Changed,
+            TTViewState::Processing,
+            Ordering::SeqCst,
+            Ordering::Acquire,
+        )
+        {
+            if let Some(input) = input_data
+            {
+                let settings = (*self.settings).clone();
+                match TTPixelPlots::new(
+                    input,
+                    fourier.as_ref(),
+                    lazy_cwt.as_ref(),
+                    wavelet_bank,
+                    self.wavelet.load(Relaxed),
+                    &settings,
+                    cancel,
+                )
+                {
+                    Some(plots) =>
+                    {
+                        self.plots.write(plots);
+                        let _ = self.state.compare_exchange(
+                            TTViewState::Processing,
+                            TTViewState::Valid,
+                            Ordering::SeqCst,
+                            Ordering::Acquire,
+                        );
+                    }
+                    None =>
+                    {
+                        //cancelled, plots are recalculated later
+                        self.state.store(TTViewState::Changed, Relaxed);
+                    }
+                }
+            }
+        }
+    }
+    ///next processing stage finished, so plots can show its curves
+    fn refresh(&self) -> ()
+    {
+        let _ = self.state.compare_exchange(
+            TTViewState::Valid,
+            TTViewState::Changed,
+            Ordering::SeqCst,
+            Ordering::Acquire,
+        );
+    }
+}
 impl TTStateBackend
 {
     pub fn new(
         views : [TTViewBackend; 4],
+        plot : TTPlotBackend,
         changed : Arc<(Mutex<bool>, Condvar)>,
         stop_flag : Arc<AtomicBool>,
         state : Arc<AtomicFileState>,
         path : tribuf::Output<Option<TTFile>>,
+        info : Arc<RwLock<TTFileInfo>>,
         settings : Arc<GlobalSettings>,
     ) -> Self
     {
         Self {
             views,
+            plot,
             changed,
+            view_cancel : TTCancelToken::new(stop_flag.clone(), state.clone(), VIEW_STAGES),
             stop_flag,
-            file : TTFileBackend::new(state, path),
+            file : TTFileBackend::new(state, path, info),
             wavelet_bank : WaveletBank::new_wb(),
             settings,
         }
@@ -459,30 +719,49 @@ impl TTStateBackend
                 {
                     self.file.data.lazy_cwt = None;
                     self.file.data.fourier = None;
+                    self.file.data.pct = None;
+                    self.file.data.tsr = None;
+                    self.file.stored = false;
+                    *self.file.info.write() = Default::default();
                     self.file.path.update();
                     if let Some(ref mut path) = self.file.path.output_buffer()
                     {
                         exec_time.start();
-                        self.file.data.input_data = path.data_load(self.file.state.clone());
+                        let progress = self.file.info.read().progress.clone();
+                        //loading is aborted when file selection changes or app is closing
+                        let cancel = TTCancelToken::new(
+                            self.stop_flag.clone(),
+                            self.file.state.clone(),
+                            &[FileState::Loading],
+                        );
+                        self.file.data.input_data =
+                            match path.data_load(self.file.state.clone(), &cancel, &progress)
+                            {
+                                Ok(input) => Some(input),
+                                Err(error) =>
+                                {
+                                    self.file.info.write().load_error = Some(error);
+                                    None
+                                }
+                            };
                         exec_time.stop_print("file loading time");
                         if let Some(input) = &self.file.data.input_data
                         {
                             //file loaded correctly
+                            self.file.info.write().metadata = input.metadata.clone();
+                            if let Some(frame_rate) = input.metadata.frame_rate
+                            {
+                                *self.settings.frame_rate.write() = frame_rate;
+                            }
                             let mut size = self.settings.full_size.write();
-                            *size = [
-                                input.width - 1,
-                                input.height - 1,
-                                input.frames - 1,
-                                input.frames - 1,
-                                input.frames / 2,
-                            ]
-                            .into_dimension();
+                            *size = input.full_size();
                             *self.settings.roi_min.write() = [
                                 size[0] / 8,
                                 size[1] / 8,
                                 size[2] / 8,
                                 size[3] / 8,
                                 size[4] / 8,
+                                size[5] / 8,
                             ]
                             .into_dimension();
                             *self.settings.roi_max.write() = [
@@ -491,9 +770,13 @@ impl TTStateBackend
                                 7 * size[2] / 8,
                                 7 * size[3] / 8,
                                 7 * size[4] / 8,
+                                7 * size[5] / 8,
                             ]
                             .into_dimension();
-                            *self.settings.crossection.write() = [0, 0, 0, 0, 0].into_dimension();
+                            self.settings.fft_len.store(input.data.dim().2, Relaxed);
+                            *self.settings.crossection.write() =
+                                [0, 0, 0, 0, 0, 0].into_dimension();
+                            self.settings.reference_frame.store(0, Relaxed);
 
                             let _ = self.file.state.compare_exchange(
                                 FileState::Loading,
@@ -516,6 +799,18 @@ impl TTStateBackend
                 FileState::Loaded =>
                     //this state is only for gui to acknowlege processing completed
                     {}
+                FileState::Reprocessing =>
+                {
+                    //processing settings changed, drop outdated results & start over
+                    self.file.data.lazy_cwt = None;
+                    self.file.data.fourier = None;
+                    let _ = self.file.state.compare_exchange(
+                        FileState::Reprocessing,
+                        FileState::ProcessingFourier,
+                        Ordering::SeqCst,
+                        Ordering::Acquire,
+                    );
+                }
                 FileState::ProcessingFourier =>
                 {
                     if let Some(input) = &self.file.data.input_data
@@ -528,20 +823,45 @@ impl TTStateBackend
                                 while FileState::ProcessingFourier == self.file.state.load(Relaxed)
                                     && self.stop_flag.load(Relaxed) == false
                                 {
+                                    self.plot.check_update(
+                                        &self.file.data.input_data,
+                                        &None,
+                                        &None,
+                                        &mut self.wavelet_bank,
+                                        &self.view_cancel,
+                                    );
                                     for view in &mut self.views
                                     {
                                         view.freeze();
                                         view.time_view_check_update(&self.file.data.input_data);
+                                        view.lock_in_view_check_update(&self.file.data.input_data);
+                                        view.pct_view_check_update(
+                                            &self.file.data.input_data,
+                                            &mut self.file.data.pct,
+                                        );
+                                        view.tsr_view_check_update(
+                                            &self.file.data.input_data,
+                                            &mut self.file.data.tsr,
+                                        );
                                         view.true_wavelet_view_check_update(
                                             &self.file.data.input_data,
                                             &mut self.wavelet_bank,
+                                            &self.view_cancel,
                                         );
                                     }
                                 }
                             },
                             || {
-                                self.file.data.fourier =
-                                    TTFourier::new(&input, self.file.state.clone());
+                                let cancel = TTCancelToken::new(
+                                    self.stop_flag.clone(),
+                                    self.file.state.clone(),
+                                    &[FileState::ProcessingFourier],
+                                );
+                                self.file.data.fourier = TTFourier::new(
+                                    &input,
+                                    &self.settings.processing_params(),
+                                    &cancel,
+                                );
 
                                 if let Some(_) = &self.file.data.fourier
                                 {
@@ -567,27 +887,49 @@ impl TTStateBackend
                     if let Some(fourier) = &self.file.data.fourier
                     {
                         exec_time.start();
+                        self.plot.refresh();
                         rayon::join(
                             || {
                                 //continously update time views if necessary
                                 while FileState::ProcessingWavelet == self.file.state.load(Relaxed)
                                     && self.stop_flag.load(Relaxed) == false
                                 {
+                                    self.plot.check_update(
+                                        &self.file.data.input_data,
+                                        &self.file.data.fourier,
+                                        &None,
+                                        &mut self.wavelet_bank,
+                                        &self.view_cancel,
+                                    );
                                     for view in &mut self.views
                                     {
                                         view.freeze();
                                         view.time_view_check_update(&self.file.data.input_data);
+                                        view.lock_in_view_check_update(&self.file.data.input_data);
+                                        view.pct_view_check_update(
+                                            &self.file.data.input_data,
+                                            &mut self.file.data.pct,
+                                        );
+                                        view.tsr_view_check_update(
+                                            &self.file.data.input_data,
+                                            &mut self.file.data.tsr,
+                                        );
                                         view.fourier_view_check_update(&self.file.data.fourier);
                                         view.true_wavelet_view_check_update(
                                             &self.file.data.input_data,
                                             &mut self.wavelet_bank,
+                                            &self.view_cancel,
                                         );
                                     }
                                 }
                             },
                             || {
-                                self.file.data.lazy_cwt =
-                                    TTLazyCWT::new(&fourier, self.file.state.clone());
+                                let cancel = TTCancelToken::new(
+                                    self.stop_flag.clone(),
+                                    self.file.state.clone(),
+                                    &[FileState::ProcessingWavelet],
+                                );
+                                self.file.data.lazy_cwt = TTLazyCWT::new(&fourier, &cancel);
 
                                 if let Some(_) = &self.file.data.lazy_cwt
                                 {
@@ -611,24 +953,48 @@ impl TTStateBackend
                 FileState::ReadySaving =>
                 {
                     exec_time.start();
+                    self.plot.refresh();
                     rayon::join(
                         || {
                             //continously update time views if necessary
                             while FileState::ReadySaving == self.file.state.load(Relaxed)
                                 && self.stop_flag.load(Relaxed) == false
                             {
+                                self.plot.check_update(
+                                    &self.file.data.input_data,
+                                    &self.file.data.fourier,
+                                    &self.file.data.lazy_cwt,
+                                    &mut self.wavelet_bank,
+                                    &self.view_cancel,
+                                );
                                 for view in &mut self.views
                                 {
                                     view.freeze();
                                     view.time_view_check_update(&self.file.data.input_data);
+                                    view.lock_in_view_check_update(&self.file.data.input_data);
+                                    view.pct_view_check_update(
+                                        &self.file.data.input_data,
+                                        &mut self.file.data.pct,
+                                    );
+                                    view.tsr_view_check_update(
+                                        &self.file.data.input_data,
+                                        &mut self.file.data.tsr,
+                                    );
                                     view.wavelet_view_check_update(
                                         &self.file.data.lazy_cwt,
                                         &mut self.wavelet_bank,
+                                        &self.view_cancel,
+                                    );
+                                    view.composite_view_check_update(
+                                        &self.file.data.lazy_cwt,
+                                        &mut self.wavelet_bank,
+                                        &self.view_cancel,
                                     );
                                     view.fourier_view_check_update(&self.file.data.fourier);
                                     view.true_wavelet_view_check_update(
                                         &self.file.data.input_data,
                                         &mut self.wavelet_bank,
+                                        &self.view_cancel,
                                     );
                                 }
                             }
@@ -638,8 +1004,14 @@ impl TTStateBackend
                             {
                                 if let Some(ref data) = self.file.data.input_data
                                 {
-                                    let _ = path.data_store(data);
-                                    exec_time.stop_print("saving to file");
+                                    let params = self.settings.store_params();
+                                    if let Some(result) =
+                                        store_once(&mut self.file.stored, path, data, &params)
+                                    {
+                                        self.file.info.write().store_error =
+                                            result.err().map(|e| e.to_string());
+                                        exec_time.stop_print("saving to file");
+                                    }
                                     //file processed correctly
                                     let _ = self.file.state.compare_exchange(
                                         FileState::ReadySaving,
@@ -654,18 +1026,41 @@ impl TTStateBackend
                 }
                 FileState::Ready =>
                 {
+                    self.plot.check_update(
+                        &self.file.data.input_data,
+                        &self.file.data.fourier,
+                        &self.file.data.lazy_cwt,
+                        &mut self.wavelet_bank,
+                        &self.view_cancel,
+                    );
                     for view in &mut self.views
                     {
                         view.freeze();
                         view.time_view_check_update(&self.file.data.input_data);
+                        view.lock_in_view_check_update(&self.file.data.input_data);
+                        view.pct_view_check_update(
+                            &self.file.data.input_data,
+                            &mut self.file.data.pct,
+                        );
+                        view.tsr_view_check_update(
+                            &self.file.data.input_data,
+                            &mut self.file.data.tsr,
+                        );
                         view.wavelet_view_check_update(
                             &self.file.data.lazy_cwt,
                             &mut self.wavelet_bank,
+                            &self.view_cancel,
+                        );
+                        view.composite_view_check_update(
+                            &self.file.data.lazy_cwt,
+                            &mut self.wavelet_bank,
+                            &self.view_cancel,
                         );
                         view.fourier_view_check_update(&self.file.data.fourier);
                         view.true_wavelet_view_check_update(
                             &self.file.data.input_data,
                             &mut self.wavelet_bank,
+                            &self.view_cancel,
                         );
                     }
                     self.check_changed_and_sleep();
@@ -674,3 +1069,70 @@ impl TTStateBackend
         }
     }
 }
+
+//=======================================
+//================Helpers================
+//=======================================
+
+///converts `data` loaded from `file` to ttcf, unless it has been already `stored` since loading
+///(first conversion may delete/archive source, so it can not be repeated after reprocessing)
+fn store_once(
+    stored : &mut bool,
+    file : &TTFile,
+    data : &TTInputData,
+    params : &TTStoreParams,
+) -> Option<io::Result<()>>
+{
+    if *stored
+    {
+        return None;
+    }
+    *stored = true;
+    Some(file.data_store(data, params))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::fs;
+
+    ///2 frames of 3x2 px
+    const TEXT : &str = "Frame: 1\n0 1 2\n0 1.0 2.0 3.0\n1 4.0 5.0 6.0\nFrame: 2\n0 1 2\n0 1.5 \
+                         2.5 3.5\n1 4.5 5.5 6.5\n";
+
+    #[test]
+    fn reprocessing_does_not_store_again()
+    {
+        for policy in [SourcePolicy::Delete, SourcePolicy::Archive]
+        {
+            let dir = std::env::temp_dir().join(format!("tt_store_once_{}", policy.as_ref()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let source = dir.join("scan.txt");
+            fs::write(&source, TEXT).unwrap();
+            let mut file = TTFile::from(source.clone().into_os_string());
+            let state = Arc::new(AtomicFileState::new(FileState::Loading));
+            let data = file
+                .data_load(state, &Default::default(), &Default::default())
+                .unwrap();
+            let params = TTStoreParams {
+                precision :     TTCFPrecision::F32,
+                source_policy : policy,
+                archive_dir :   "archive".into(),
+            };
+
+            let mut stored = false;
+            assert!(store_once(&mut stored, &file, &data, &params)
+                .unwrap()
+                .is_ok());
+            assert!(!source.exists());
+            assert!(dir.join("scan.ttcf").exists());
+            //pass after reprocessing does not touch ttcf (storing again would fail without source)
+            assert!(store_once(&mut stored, &file, &data, &params).is_none());
+            assert!(file.data_store(&data, &params).is_err());
+            assert!(dir.join("scan.ttcf").exists());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
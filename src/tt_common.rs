@@ -1,22 +1,31 @@
 use atomic_enum::atomic_enum;
 use egui::mutex::RwLock;
-use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use egui::{Color32, ColorImage, Context, TextureHandle, TextureOptions};
 use lazy_static::*;
-use ndarray::{Array3, Axis, Ix5};
+use ndarray::{s, Array2, Array3, ArrayView2, Axis, IntoDimension, Ix6};
+use rayon::slice::ParallelSliceMut;
+use std::collections::HashMap;
 
+use crate::gap_window::{AtomicWindowType, WindowType};
+use crate::tt_pct::TTPct;
+use crate::tt_tsr::TSR_DEFAULT_DEGREE;
 use crate::wavelet::AtomicWaveletType;
 use crate::wavelet::WaveletType;
+use std::f64::consts::PI;
 #[cfg(feature = "time_meas")]
 use std::fs::File;
+use std::io;
 #[cfg(feature = "time_meas")]
 use std::io::BufWriter;
 #[cfg(feature = "time_meas")]
 use std::io::Write;
 use std::iter;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicUsize};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
@@ -34,6 +43,7 @@ pub enum FileState
     New,
     Loading,
     Loaded,
+    Reprocessing,
     ProcessingFourier,
     ProcessingWavelet,
     ReadySaving,
@@ -48,8 +58,14 @@ pub enum TTAxis
     T = 2,
     S = 3,
     F = 4,
+    ///principal component
+    C = 5,
 }
 pub const AXIS_T : Axis = Axis(TTAxis::T as usize);
+///used when frame rate is unknown
+pub const DEFAULT_FRAME_RATE : f64 = 50.0;
+///archive subdirectory (next to source file) for `SourcePolicy::Archive`
+pub const DEFAULT_ARCHIVE_DIR : &str = "archive";
 
 #[atomic_enum]
 #[derive(
@@ -58,20 +74,44 @@ pub const AXIS_T : Axis = Axis(TTAxis::T as usize);
 #[strum(serialize_all = "title_case")]
 pub enum ViewModeDomain
 {
-    TimeView        = 0,
-    FourierView     = 1,
-    FastWaveletView = 2,
-    WaveletView     = 3,
+    TimeView             = 0,
+    FourierView          = 1,
+    FastWaveletView      = 2,
+    WaveletView          = 3,
+    CompositeWaveletView = 4,
+    #[strum(serialize = "PCT View")]
+    PCTView              = 5,
+    #[strum(serialize = "TSR View")]
+    TSRView              = 6,
+    LockInView           = 7,
 }
 pub struct ViewMode
 {
-    pub domain :        AtomicViewModeDomain,
-    pub position :      RwLock<Ix5>,
-    pub bind_position : AtomicBool,
-    pub mode_counter :  AtomicUsize,
-    pub wavelet :       AtomicWaveletType,
-    pub display_mode :  AtomicComplexResultMode,
-    pub denoise :       AtomicBool,
+    pub domain :         AtomicViewModeDomain,
+    pub position :       RwLock<Ix6>,
+    pub bind_position :  AtomicBool,
+    pub mode_counter :   AtomicUsize,
+    pub wavelet :        AtomicWaveletType,
+    pub display_mode :   AtomicComplexResultMode,
+    pub denoise :        AtomicBool,
+    pub differential :   AtomicBool,
+    pub composite_mode : AtomicCompositeMode,
+    ///range of scales(inclusive) combined in composite view
+    pub scale_band :     RwLock<[usize; 2]>,
+    pub tsr_mode :       AtomicTSRMode,
+    ///degree of polynomial fitted in TSR view
+    pub tsr_degree :     AtomicUsize,
+    ///excitation frequency [Hz] demodulated in lock-in view
+    pub lockin_freq :    RwLock<f64>,
+    pub lockin_method :  AtomicLockInMethod,
+    pub scale_mode :     AtomicScaleMode,
+    ///min & max of `ScaleMode::Fixed`
+    pub scale_range :    RwLock<[f64; 2]>,
+    ///percentage of values clipped at each end in `ScaleMode::Percentile`
+    pub clip_percent :   RwLock<f64>,
+    pub color_map :      AtomicColorMap,
+    ///reverse `color_map` (phase views always use cyclic phase gradient)
+    pub reverse_colors : AtomicBool,
 }
 
 #[atomic_enum]
@@ -85,6 +125,120 @@ pub enum ComplexResultMode
     Real,
     Imaginary,
 }
+///method of combining wavelet transforms at different scales
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum CompositeMode
+{
+    #[default]
+    MagnitudeSum,
+    MaxMagnitudeScale,
+    MaxEnergyPhase,
+}
+///mapping of view values to colors
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum ScaleMode
+{
+    ///histogram equalization (roi is over-represented); time slices are normalized
+    #[default]
+    Equalized,
+    ///linear between min & max value
+    Linear,
+    ///linear between user set min & max
+    Fixed,
+    ///linear, symmetric around zero
+    Symmetric,
+    ///linear between percentiles (extreme values are clipped)
+    Percentile,
+    ///logarithmic between smallest positive & max value
+    Log,
+    ///linear in range spanning values of all linked views (`GlobalSettings::linked_range`)
+    Linked,
+}
+///gradient of non-phase views
+#[atomic_enum]
+#[derive(PartialEq, Eq, Hash, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum ColorMap
+{
+    #[default]
+    Inferno,
+    Grey,
+    ///black-violet-orange-yellow-white palette of thermal cameras
+    Ironbow,
+    ///blue-cyan-green-yellow-red
+    Rainbow,
+    Viridis,
+    Turbo,
+    ///diverging red-white-blue
+    #[strum(serialize = "RdBu")]
+    RdBu,
+    ///loaded from file by `TTGradients::load_custom()`
+    Custom,
+}
+///TSR view content; derivatives are taken with respect to ln(t)
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum TSRMode
+{
+    #[default]
+    Reconstruction    = 0,
+    First_Derivative  = 1,
+    Second_Derivative = 2,
+}
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum LockInMethod
+{
+    ///correlation with sine & cosine references over integer number of periods
+    #[default]
+    Correlation,
+    ///4 samples per period (averaged over all periods)
+    Four_Point,
+}
+///precision of values stored in ttcf file
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum TTCFPrecision
+{
+    ///f32 with 10 LSb of significand truncated (best compression)
+    #[default]
+    Lossy_22_Bit,
+    #[strum(serialize = "f32")]
+    F32,
+    #[strum(serialize = "f64")]
+    F64,
+}
+///what happens to source file after successful conversion to ttcf
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum SourcePolicy
+{
+    #[default]
+    Keep,
+    Delete,
+    ///move to archive directory
+    Archive,
+}
+///physical quantity displayed for wavelet scale axis
+#[atomic_enum]
+#[derive(PartialEq, Default, strum_macros::AsRefStr, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum ScaleUnit
+{
+    ///equivalent Fourier period [s]
+    #[default]
+    Period,
+    ///equivalent Fourier frequency [Hz]
+    Frequency,
+}
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct RangedVal
 {
@@ -112,13 +266,29 @@ pub enum SelectMode
 }
 pub struct GlobalSettings
 {
-    pub roi_min :     RwLock<Ix5>,
-    pub roi_max :     RwLock<Ix5>,
-    pub crossection : RwLock<Ix5>,
-    pub full_size :   RwLock<Ix5>,
-    pub roi_zoom :    AtomicBool,
-    pub select_mode : AtomicSelectMode,
-    changed :         AtomicBool,
+    pub roi_min :         RwLock<Ix6>,
+    pub roi_max :         RwLock<Ix6>,
+    pub crossection :     RwLock<Ix6>,
+    pub full_size :       RwLock<Ix6>,
+    pub roi_zoom :        AtomicBool,
+    pub select_mode :     AtomicSelectMode,
+    ///frame subtracted from sequence in differential views/pre-processing
+    pub reference_frame : AtomicUsize,
+    ///subtract `reference_frame` from input data before Fourier & wavelet processing
+    pub diff_preprocess : AtomicBool,
+    pub window :          AtomicWindowType,
+    ///acquisition frame rate [Hz]
+    pub frame_rate :      RwLock<f64>,
+    ///length of Fourier transform (frames with padding)
+    pub fft_len :         AtomicUsize,
+    pub scale_unit :      AtomicScaleUnit,
+    ///precision of ttcf file written after processing of other file types
+    pub ttcf_precision :  AtomicTTCFPrecision,
+    pub source_policy :   AtomicSourcePolicy,
+    pub archive_dir :     RwLock<PathBuf>,
+    ///min & max of values of all views in `ScaleMode::Linked` (updated by gui when any of them changes)
+    pub linked_range :    RwLock<[f64; 2]>,
+    changed :             AtomicBool,
 }
 #[atomic_enum]
 #[derive(PartialEq)]
@@ -132,17 +302,47 @@ pub enum TTViewState
 #[derive(Clone)]
 pub struct Thermogram
 {
-    pub image :  TextureHandle,
-    pub legend : TTGradients,
-    pub scale :  [f64; 33],
+    pub image :     TextureHandle,
+    ///CPU side copy of `image`
+    pub pixels :    ColorImage,
+    pub legend :    TTGradients,
+    pub scale :     [f64; 33],
+    ///values before color mapping
+    pub raw :       TTRawView,
+    ///min & max of finite `raw` values
+    pub range :     Option<[f64; 2]>,
+    ///params with which thermogram was generated
+    pub view_mode : ViewMode,
+    pub settings :  GlobalSettings,
+}
+
+///raw (not color mapped) 2D view, indexed [horizontal, vertical]
+#[derive(Clone)]
+pub struct TTRawView
+{
+    pub data :   Array2<f64>,
+    ///horizontal & vertical axis
+    pub axes :   [TTAxis; 2],
+    ///index (in whole sequence) of first element along each axis; non zero for roi zoomed views
+    pub offset : [usize; 2],
 }
 
-#[derive(Clone, PartialEq, strum_macros::AsRefStr)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TTGradients
 {
-    Linear,
+    ///color map & whether it is reversed
+    Linear(ColorMap, bool),
     Phase,
 }
+///gradients & legend textures, created on first use
+#[derive(Default)]
+struct TTGradientRegistry
+{
+    gradients : HashMap<TTGradients, Arc<colorgrad::Gradient>>,
+    legends :   HashMap<TTGradients, TextureHandle>,
+    ///colors of `ColorMap::Custom`; empty until loaded
+    custom :    Vec<colorgrad::Color>,
+}
 
 pub struct ExecutionTimeMeas
 {
@@ -151,13 +351,61 @@ pub struct ExecutionTimeMeas
     #[cfg(feature = "time_meas")]
     writer :          BufWriter<File>,
 }
+///file-level processing settings (plain counterpart of processing related `GlobalSettings`)
+#[derive(Clone, PartialEq, Default)]
+pub struct TTProcessingParams
+{
+    ///if set, this frame is subtracted from whole sequence before transform (differential pre-processing)
+    pub diff_reference : Option<usize>,
+    ///apodisation window used in Fourier transform (and de-windowing of its integrals)
+    pub window :         WindowType,
+}
+///acquisition metadata read from input file headers; unknown values are `None`/empty
+#[derive(Clone, PartialEq, Default)]
+pub struct TTMetadata
+{
+    ///acquisition frame rate [Hz]; given in header or derived from timestamps
+    pub frame_rate :  Option<f64>,
+    ///acquisition time of each frame [s], relative to first frame (empty if not available)
+    pub timestamps :  Vec<f64>,
+    pub camera :      Option<String>,
+    pub emissivity :  Option<f64>,
+    ///unit of pixel values
+    pub units :       Option<String>,
+    ///all file header entries (key, value) in file order; source of above fields
+    pub entries :     Vec<(String, String)>,
+    ///name & content hash of file converted to ttcf
+    pub source_name : Option<String>,
+    pub source_hash : Option<u64>,
+}
+///conversion to ttcf settings (plain counterpart of storing related `GlobalSettings`)
+#[derive(Clone, PartialEq)]
+pub struct TTStoreParams
+{
+    pub precision :     TTCFPrecision,
+    pub source_policy : SourcePolicy,
+    ///target of `SourcePolicy::Archive`; relative path is resolved against source file directory
+    pub archive_dir :   PathBuf,
+}
+///fraction (0-1) of long operation (eg. file loading) that is done; written by backend & displayed by GUI
+#[derive(Default)]
+pub struct TTProgress(AtomicU32);
+///checked inside long parallel loops, so processing can be aborted promptly;
+///token is cancelled when app is closing or file state leaves given stages, default token is never cancelled
+#[derive(Clone, Default)]
+pub struct TTCancelToken
+{
+    stop_flag :  Option<Arc<AtomicBool>>,
+    file_state : Option<(Arc<AtomicFileState>, &'static [FileState])>,
+}
 #[derive(PartialEq)]
 pub struct TTInputData
 {
-    pub frames : usize,
-    pub width :  usize,
-    pub height : usize,
-    pub data :   Array3<f64>,
+    pub frames :   usize,
+    pub width :    usize,
+    pub height :   usize,
+    pub data :     Array3<f64>,
+    pub metadata : TTMetadata,
 }
 //=======================================
 //=====Traits & Trait Implementations====
@@ -185,15 +433,45 @@ impl Into<char> for TTAxis
 {
     fn into(self) -> char
     {
-        const ttaxis_char : [char; 5] = ['X', 'Y', 't', 's', 'f'];
+        const ttaxis_char : [char; 6] = ['X', 'Y', 't', 's', 'f', 'c'];
         ttaxis_char[self as usize]
     }
 }
+impl TryFrom<char> for TTAxis
+{
+    type Error = ();
+
+    fn try_from(value : char) -> Result<Self, Self::Error>
+    {
+        match value.to_ascii_lowercase()
+        {
+            'x' => Ok(TTAxis::X),
+            'y' => Ok(TTAxis::Y),
+            't' => Ok(TTAxis::T),
+            's' => Ok(TTAxis::S),
+            'f' => Ok(TTAxis::F),
+            'c' => Ok(TTAxis::C),
+            _ => Err(()),
+        }
+    }
+}
+impl TTAxis
+{
+    ///positions along scale & component axes are displayed 1-based
+    pub fn display_offset(self) -> usize { matches!(self, TTAxis::S | TTAxis::C) as usize }
+}
 impl Into<&str> for TTAxis
 {
     fn into(self) -> &'static str
     {
-        const ttaxis_char : [&str; 5] = ["| X:", "| Y:", "| frame:", "| scale:", "| freq:"];
+        const ttaxis_char : [&str; 6] = [
+            "| X:",
+            "| Y:",
+            "| frame:",
+            "| scale:",
+            "| freq:",
+            "| component:",
+        ];
         ttaxis_char[self as usize]
     }
 }
@@ -202,13 +480,25 @@ impl Clone for ViewMode
     fn clone(&self) -> Self
     {
         Self {
-            domain :        AtomicViewModeDomain::new(self.domain.load(Relaxed)),
-            position :      RwLock::new(*self.position.read()),
-            bind_position : AtomicBool::new(self.bind_position.load(Relaxed)),
-            mode_counter :  AtomicUsize::new(self.mode_counter.load(Relaxed)),
-            wavelet :       AtomicWaveletType::new(self.wavelet.load(Relaxed)),
-            display_mode :  AtomicComplexResultMode::new(self.display_mode.load(Relaxed)),
-            denoise :       AtomicBool::new(self.denoise.load(Relaxed)),
+            domain :         AtomicViewModeDomain::new(self.domain.load(Relaxed)),
+            position :       RwLock::new(*self.position.read()),
+            bind_position :  AtomicBool::new(self.bind_position.load(Relaxed)),
+            mode_counter :   AtomicUsize::new(self.mode_counter.load(Relaxed)),
+            wavelet :        AtomicWaveletType::new(self.wavelet.load(Relaxed)),
+            display_mode :   AtomicComplexResultMode::new(self.display_mode.load(Relaxed)),
+            denoise :        AtomicBool::new(self.denoise.load(Relaxed)),
+            differential :   AtomicBool::new(self.differential.load(Relaxed)),
+            composite_mode : AtomicCompositeMode::new(self.composite_mode.load(Relaxed)),
+            scale_band :     RwLock::new(*self.scale_band.read()),
+            tsr_mode :       AtomicTSRMode::new(self.tsr_mode.load(Relaxed)),
+            tsr_degree :     AtomicUsize::new(self.tsr_degree.load(Relaxed)),
+            lockin_freq :    RwLock::new(*self.lockin_freq.read()),
+            lockin_method :  AtomicLockInMethod::new(self.lockin_method.load(Relaxed)),
+            scale_mode :     AtomicScaleMode::new(self.scale_mode.load(Relaxed)),
+            scale_range :    RwLock::new(*self.scale_range.read()),
+            clip_percent :   RwLock::new(*self.clip_percent.read()),
+            color_map :      AtomicColorMap::new(self.color_map.load(Relaxed)),
+            reverse_colors : AtomicBool::new(self.reverse_colors.load(Relaxed)),
         }
     }
 }
@@ -217,13 +507,23 @@ impl Clone for GlobalSettings
     fn clone(&self) -> Self
     {
         Self {
-            roi_min :     RwLock::new(*self.roi_min.read()),
-            roi_max :     RwLock::new(*self.roi_max.read()),
-            crossection : RwLock::new(*self.crossection.read()),
-            full_size :   RwLock::new(*self.full_size.read()),
-            roi_zoom :    AtomicBool::new(self.roi_zoom.load(Relaxed)),
-            select_mode : AtomicSelectMode::new(self.select_mode.load(Relaxed)),
-            changed :     AtomicBool::new(self.changed.load(Relaxed)),
+            roi_min :         RwLock::new(*self.roi_min.read()),
+            roi_max :         RwLock::new(*self.roi_max.read()),
+            crossection :     RwLock::new(*self.crossection.read()),
+            full_size :       RwLock::new(*self.full_size.read()),
+            roi_zoom :        AtomicBool::new(self.roi_zoom.load(Relaxed)),
+            select_mode :     AtomicSelectMode::new(self.select_mode.load(Relaxed)),
+            reference_frame : AtomicUsize::new(self.reference_frame.load(Relaxed)),
+            diff_preprocess : AtomicBool::new(self.diff_preprocess.load(Relaxed)),
+            window :          AtomicWindowType::new(self.window.load(Relaxed)),
+            frame_rate :      RwLock::new(*self.frame_rate.read()),
+            fft_len :         AtomicUsize::new(self.fft_len.load(Relaxed)),
+            scale_unit :      AtomicScaleUnit::new(self.scale_unit.load(Relaxed)),
+            ttcf_precision :  AtomicTTCFPrecision::new(self.ttcf_precision.load(Relaxed)),
+            source_policy :   AtomicSourcePolicy::new(self.source_policy.load(Relaxed)),
+            archive_dir :     RwLock::new(self.archive_dir.read().clone()),
+            linked_range :    RwLock::new(*self.linked_range.read()),
+            changed :         AtomicBool::new(self.changed.load(Relaxed)),
         }
     }
 }
@@ -234,7 +534,7 @@ mod tt_axis
 
     impl ViewMode
     {
-        pub const ViewModeAxes : [&[([TTAxis; 2], &'static [TTAxis])]; 4] = [
+        pub const ViewModeAxes : [&[([TTAxis; 2], &'static [TTAxis])]; 8] = [
             &[([X, Y], &[T]), ([X, T], &[Y]), ([T, Y], &[X])], /*time*/
             &[([X, Y], &[F]), ([X, F], &[Y]), ([F, Y], &[X])], /*fourier*/
             &[
@@ -253,6 +553,15 @@ mod tt_axis
                 ([S, Y], &[T, X]),
                 ([T, S], &[X, Y]),
             ], /*true wavelet*/
+            &[([X, Y], &[T]), ([X, T], &[Y]), ([T, Y], &[X])], /*composite wavelet*/
+            &[
+                ([X, Y], &[C]),
+                ([X, C], &[Y]),
+                ([C, Y], &[X]),
+                ([T, C], &[]),
+            ], /*PCT*/
+            &[([X, Y], &[T]), ([X, T], &[Y]), ([T, Y], &[X])], /*TSR*/
+            &[([X, Y], &[])],                                  /*lock-in*/
         ];
     }
 }
@@ -265,15 +574,35 @@ impl ViewMode
     ) -> Self
     {
         Self {
-            domain :        AtomicViewModeDomain::new(domain),
-            position :      Default::default(),
-            bind_position : Default::default(),
-            mode_counter :  Default::default(),
-            wavelet :       AtomicWaveletType::new(wavelet),
-            display_mode :  AtomicComplexResultMode::new(display_mode),
-            denoise :       Default::default(),
+            domain :         AtomicViewModeDomain::new(domain),
+            position :       Default::default(),
+            bind_position :  Default::default(),
+            mode_counter :   Default::default(),
+            wavelet :        AtomicWaveletType::new(wavelet),
+            display_mode :   AtomicComplexResultMode::new(display_mode),
+            denoise :        Default::default(),
+            differential :   Default::default(),
+            composite_mode : AtomicCompositeMode::new(Default::default()),
+            scale_band :     RwLock::new([0, 15]),
+            tsr_mode :       AtomicTSRMode::new(Default::default()),
+            tsr_degree :     AtomicUsize::new(TSR_DEFAULT_DEGREE),
+            lockin_freq :    RwLock::new(1.0),
+            lockin_method :  AtomicLockInMethod::new(Default::default()),
+            scale_mode :     AtomicScaleMode::new(Default::default()),
+            scale_range :    RwLock::new([0.0, 1.0]),
+            clip_percent :   RwLock::new(1.0),
+            color_map :      AtomicColorMap::new(Default::default()),
+            reverse_colors : Default::default(),
         }
     }
+    ///gradient of non-phase views
+    pub fn gradient(&self) -> TTGradients
+    {
+        TTGradients::Linear(
+            self.color_map.load(Relaxed),
+            self.reverse_colors.load(Relaxed),
+        )
+    }
     pub fn get_view_axes(&self) -> [TTAxis; 2]
     {
         Self::ViewModeAxes[self.domain.load(Ordering::Relaxed) as usize]
@@ -299,18 +628,67 @@ impl Default for RangedVal
     }
 }
 
+impl TTProgress
+{
+    pub fn set(&self, fraction : f32) { self.0.store(fraction.to_bits(), Ordering::Relaxed) }
+    pub fn get(&self) -> f32 { f32::from_bits(self.0.load(Ordering::Relaxed)) }
+}
+impl TTCancelToken
+{
+    pub fn new(
+        stop_flag : Arc<AtomicBool>,
+        file_state : Arc<AtomicFileState>,
+        stages : &'static [FileState],
+    ) -> Self
+    {
+        Self {
+            stop_flag :  Some(stop_flag),
+            file_state : Some((file_state, stages)),
+        }
+    }
+    pub fn is_cancelled(&self) -> bool
+    {
+        self.stop_flag
+            .as_ref()
+            .map_or(false, |stop| stop.load(Ordering::Relaxed))
+            || self.file_state.as_ref().map_or(false, |(state, stages)| {
+                !stages.contains(&state.load(Ordering::Relaxed))
+            })
+    }
+}
+impl Default for TTStoreParams
+{
+    fn default() -> Self
+    {
+        Self {
+            precision :     Default::default(),
+            source_policy : Default::default(),
+            archive_dir :   DEFAULT_ARCHIVE_DIR.into(),
+        }
+    }
+}
 impl Default for GlobalSettings
 {
     fn default() -> Self
     {
         Self {
-            roi_min :     Default::default(),
-            roi_max :     Default::default(),
-            crossection : Default::default(),
-            full_size :   Default::default(),
-            roi_zoom :    Default::default(),
-            select_mode : AtomicSelectMode::new(Default::default()),
-            changed :     Default::default(),
+            roi_min :         Default::default(),
+            roi_max :         Default::default(),
+            crossection :     Default::default(),
+            full_size :       Default::default(),
+            roi_zoom :        Default::default(),
+            select_mode :     AtomicSelectMode::new(Default::default()),
+            reference_frame : Default::default(),
+            diff_preprocess : Default::default(),
+            window :          AtomicWindowType::new(Default::default()),
+            frame_rate :      RwLock::new(DEFAULT_FRAME_RATE),
+            fft_len :         Default::default(),
+            scale_unit :      AtomicScaleUnit::new(Default::default()),
+            ttcf_precision :  AtomicTTCFPrecision::new(Default::default()),
+            source_policy :   AtomicSourcePolicy::new(Default::default()),
+            archive_dir :     RwLock::new(DEFAULT_ARCHIVE_DIR.into()),
+            linked_range :    RwLock::new([0.0, 1.0]),
+            changed :         Default::default(),
         }
     }
 }
@@ -321,60 +699,439 @@ impl GlobalSettings
     {
         self.roi_min.read()[axis as usize]..self.roi_max.read()[axis as usize]
     }
+    pub fn processing_params(&self) -> TTProcessingParams
+    {
+        TTProcessingParams {
+            diff_reference : if self.diff_preprocess.load(Relaxed)
+            {
+                Some(self.reference_frame.load(Relaxed))
+            }
+            else
+            {
+                None
+            },
+            window :         self.window.load(Relaxed),
+        }
+    }
+    pub fn store_params(&self) -> TTStoreParams
+    {
+        TTStoreParams {
+            precision :     self.ttcf_precision.load(Relaxed),
+            source_policy : self.source_policy.load(Relaxed),
+            archive_dir :   self.archive_dir.read().clone(),
+        }
+    }
+    ///physical value & unit of `position` along `axis` (time, frequency & wavelet scale axes only);
+    ///scale is converted to equivalent Fourier period/frequency of `wavelet`
+    pub fn physical_position(
+        &self,
+        axis : TTAxis,
+        position : usize,
+        wavelet : WaveletType,
+    ) -> Option<(f64, &'static str)>
+    {
+        let frame_rate = *self.frame_rate.read();
+        match axis
+        {
+            TTAxis::T => Some((position as f64 / frame_rate, "s")),
+            TTAxis::F =>
+            {
+                let fft_len = self.fft_len.load(Relaxed).max(1);
+                Some((position as f64 * frame_rate / fft_len as f64, "Hz"))
+            }
+            TTAxis::S =>
+            {
+                //scale index 0 is scale 1
+                let freq =
+                    wavelet.centre_frequency() / (2.0 * PI * (position + 1) as f64) * frame_rate;
+                match self.scale_unit.load(Relaxed)
+                {
+                    ScaleUnit::Period => Some((1.0 / freq, "s")),
+                    ScaleUnit::Frequency => Some((freq, "Hz")),
+                }
+            }
+            TTAxis::X | TTAxis::Y | TTAxis::C => None,
+        }
+    }
+}
+impl TTMetadata
+{
+    ///derives known fields from header `entries` (matched by key, case insensitive);
+    ///`timestamps` [s] may be absolute, they are stored relative to first frame
+    pub fn new(entries : Vec<(String, String)>, mut timestamps : Vec<f64>) -> Self
+    {
+        let find = |keys : &[&str]| {
+            entries.iter().find_map(|(key, value)| {
+                let key = key.to_lowercase();
+                keys.iter().any(|k| key.contains(k)).then(|| value.clone())
+            })
+        };
+        if let Some(&first) = timestamps.first()
+        {
+            timestamps.iter_mut().for_each(|t| *t -= first);
+        }
+        let frame_rate = find(&["frame rate", "framerate", "fps", "frame frequency"])
+            .and_then(|x| parse_leading_f64(&x))
+            .filter(|&x| x > 0.0)
+            .or_else(|| {
+                //mean frame rate of whole sequence
+                let span = timestamps.last().copied().unwrap_or(0.0);
+                (span > 0.0).then(|| (timestamps.len() - 1) as f64 / span)
+            });
+        Self {
+            frame_rate,
+            camera : find(&["camera", "model", "device"]),
+            emissivity : find(&["emissivity"]).and_then(|x| parse_leading_f64(&x)),
+            units : find(&["unit"]),
+            timestamps,
+            entries,
+            source_name : None,
+            source_hash : None,
+        }
+    }
+    pub fn is_empty(&self) -> bool
+    {
+        self.entries.is_empty() && self.timestamps.is_empty() && self.source_name.is_none()
+    }
+}
+impl TTInputData
+{
+    ///max. position along each `TTAxis`
+    pub fn full_size(&self) -> Ix6
+    {
+        [
+            self.width - 1,
+            self.height - 1,
+            self.frames - 1,
+            self.frames - 1,
+            self.frames / 2,
+            TTPct::components(self) - 1,
+        ]
+        .into_dimension()
+    }
+    pub fn time_view(&self, params : &ViewMode, settings : &GlobalSettings) -> Array2<f64>
+    {
+        let settings_axis = params.get_settings_axes()[0] as usize;
+        let position = params.position.read()[settings_axis];
+        let input_view = self.data.index_axis(Axis(settings_axis), position);
+        let view = if params.differential.load(Relaxed)
+        {
+            //differential view: tv - tv(t=reference_frame)
+            let reference = self
+                .data
+                .index_axis(AXIS_T, settings.reference_frame.load(Relaxed));
+            if settings_axis == TTAxis::T as usize
+            {
+                &input_view - &reference
+            }
+            else
+            {
+                //X-t or t-Y; reference is single column (along T) broadcasted
+                let reference = reference.index_axis_move(Axis(settings_axis), position);
+                &input_view - &reference.insert_axis(Axis(1))
+            }
+        }
+        else
+        {
+            input_view.to_owned()
+        };
+        if settings.roi_zoom.load(Relaxed)
+        {
+            let view_axes = params.get_view_axes();
+            let roi_h = settings.get_roi(view_axes[0]);
+            let roi_v = settings.get_roi(view_axes[1]);
+            view.slice_move(s![roi_h, roi_v])
+        }
+        else
+        {
+            view
+        }
+    }
 }
 impl Thermogram
 {
     pub fn new(image : TextureHandle) -> Self
     {
         Self {
+            pixels : ColorImage::new(image.size(), Color32::TRANSPARENT),
             image,
-            legend : TTGradients::Linear,
+            legend : TTGradients::Linear(Default::default(), false),
             scale : [0.0; 33],
+            raw : TTRawView {
+                data :   Array2::zeros((0, 0)),
+                axes :   [TTAxis::X, TTAxis::Y],
+                offset : [0, 0],
+            },
+            range : None,
+            view_mode : ViewMode::new(
+                ViewModeDomain::TimeView,
+                Default::default(),
+                Default::default(),
+            ),
+            settings : Default::default(),
+        }
+    }
+}
+
+impl ColorMap
+{
+    ///`custom`- colors of `ColorMap::Custom` (inferno is used until they are loaded)
+    fn gradient(self, custom : &[colorgrad::Color], reversed : bool) -> colorgrad::Gradient
+    {
+        let from_colors = |colors : &[colorgrad::Color]| {
+            colorgrad::CustomGradient::new()
+                .colors(colors)
+                .interpolation(colorgrad::Interpolation::Linear)
+                .build()
+                .unwrap() //at least 2 colors
+        };
+        let from_html = |colors : &[&str]| {
+            colorgrad::CustomGradient::new()
+                .html_colors(colors)
+                .build()
+                .unwrap() //all colors are valid
+        };
+        let grad = match self
+        {
+            ColorMap::Inferno => colorgrad::inferno(),
+            ColorMap::Grey => from_html(&["#000000", "#ffffff"]),
+            ColorMap::Ironbow =>
+            {
+                from_html(&[
+                    "#000014", "#1a0066", "#5a0095", "#9b0f8e", "#cc2e5c", "#eb5a28", "#fa8e0a",
+                    "#fec531", "#fff3a6", "#ffffff",
+                ])
+            }
+            //`colorgrad::rainbow()` is cyclic (min & max would have same color)
+            ColorMap::Rainbow =>
+            {
+                from_html(&["#0000ff", "#00ffff", "#00ff00", "#ffff00", "#ff0000"])
+            }
+            ColorMap::Viridis => colorgrad::viridis(),
+            ColorMap::Turbo => colorgrad::turbo(),
+            ColorMap::RdBu => colorgrad::rd_bu(),
+            ColorMap::Custom if custom.len() >= 2 => from_colors(custom),
+            ColorMap::Custom => colorgrad::inferno(),
+        };
+        if reversed
+        {
+            let mut colors = grad.colors(256);
+            colors.reverse();
+            from_colors(&colors)
+        }
+        else
+        {
+            grad
         }
     }
 }
 
 impl TTGradients
 {
-    pub fn raw_grad(&self) -> &colorgrad::Gradient
+    pub fn name(&self) -> String
     {
         match self
         {
-            TTGradients::Linear => &(*linear_grad),
-            TTGradients::Phase => &(*phase_grad),
+            TTGradients::Linear(map, false) => map.as_ref().to_string(),
+            TTGradients::Linear(map, true) => format!("{} reversed", map.as_ref()),
+            TTGradients::Phase => "Phase".to_string(),
         }
     }
-    pub fn grad_legend(&self) -> &TextureHandle
+    pub fn raw_grad(&self) -> Arc<colorgrad::Gradient>
     {
-        match self
+        match *self
         {
-            TTGradients::Linear =>
-            unsafe {
-                match linear_grad_legend
-                {
-                    Some(ref inner) => inner,
-                    None => unreachable!(),
-                }
-            },
-            TTGradients::Phase =>
-            unsafe {
-                match phase_grad_legend
+            TTGradients::Linear(map, reversed) =>
+            {
+                let mut registry = gradient_registry.write();
+                let TTGradientRegistry {
+                    gradients, custom, ..
+                } = &mut *registry;
+                gradients
+                    .entry(*self)
+                    .or_insert_with(|| Arc::new(map.gradient(custom, reversed)))
+                    .clone()
+            }
+            TTGradients::Phase => phase_grad.clone(),
+        }
+    }
+    pub fn grad_legend(&self, ctx : &Context) -> TextureHandle
+    {
+        if let Some(legend) = gradient_registry.read().legends.get(self)
+        {
+            return legend.clone();
+        }
+        let legend = self.gen_legend(ctx);
+        gradient_registry
+            .write()
+            .legends
+            .entry(*self)
+            .or_insert(legend)
+            .clone()
+    }
+    ///replaces colors of `ColorMap::Custom` by colors read from text file (from min to max value),
+    ///one per line: CSS color (eg. `#ff8000`, `orange`) or "r g b" in 0-255
+    pub fn load_custom(path : &Path) -> io::Result<()>
+    {
+        let invalid = |msg : String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut colors = Vec::new();
+        for (idx, line) in std::fs::read_to_string(path)?.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.is_empty()
+            {
+                continue;
+            }
+            let rgb : Option<Vec<u8>> = line
+                .split(|c : char| c.is_whitespace() || c == ',')
+                .filter(|val| !val.is_empty())
+                .map(|val| val.parse().ok())
+                .collect();
+            let color = match rgb.as_deref()
+            {
+                Some(&[r, g, b]) => colorgrad::Color::from_rgba8(r, g, b, 255),
+                _ =>
                 {
-                    Some(ref inner) => inner,
-                    None => unreachable!(),
+                    line.parse::<colorgrad::Color>().map_err(|_| {
+                        invalid(format!("line {}: invalid color \"{}\"", idx + 1, line))
+                    })?
                 }
-            },
+            };
+            colors.push(color);
+        }
+        if colors.len() < 2
+        {
+            return Err(invalid("gradient needs at least 2 colors".to_string()));
         }
+        let is_custom =
+            |grad : &TTGradients| matches!(grad, TTGradients::Linear(ColorMap::Custom, _));
+        let mut registry = gradient_registry.write();
+        registry.custom = colors;
+        registry.gradients.retain(|grad, _| !is_custom(grad));
+        registry.legends.retain(|grad, _| !is_custom(grad));
+        Ok(())
     }
 
-    pub fn init_grad(ctx : &Context)
+    ///33 ascending values mapped to equidistant gradient positions (legend scale)
+    ///`samples`- values from which histogram is build (has to contain all values of colorized array)
+    ///`range`- min & max for `ScaleMode::Fixed` & `ScaleMode::Linked`
+    pub fn scale(
+        &self,
+        mut samples : Vec<f64>,
+        mode : ScaleMode,
+        range : [f64; 2],
+        clip_percent : f64,
+    ) -> [f64; 33]
     {
-        unsafe {
-            linear_grad_legend = Self::Linear.gen_legend(ctx);
-            phase_grad_legend = Self::Phase.gen_legend(ctx);
+        if *self == TTGradients::Phase && mode == ScaleMode::Equalized
+        {
+            //for phase gradient force -PI & PI as extreme vals
+            samples.push(-PI);
+            samples.push(PI);
         }
+        samples
+            .as_parallel_slice_mut()
+            .sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let percentile =
+            |fraction : f64| samples[((samples.len() - 1) as f64 * fraction).round() as usize];
+        let (min, max) = (samples[0], samples[samples.len() - 1]);
+        let [min, max] = match mode
+        {
+            ScaleMode::Equalized =>
+            {
+                let mut scale = [0.0; 33];
+                scale
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(idx, val)| *val = percentile(idx as f64 / 32.0));
+                return scale;
+            }
+            ScaleMode::Linear => [min, max],
+            ScaleMode::Fixed | ScaleMode::Linked =>
+            {
+                [range[0].min(range[1]), range[0].max(range[1])]
+            }
+            ScaleMode::Symmetric =>
+            {
+                let max = min.abs().max(max.abs());
+                [-max, max]
+            }
+            ScaleMode::Percentile =>
+            {
+                let fraction = clip_percent.clamp(0.0, 50.0) / 100.0;
+                [percentile(fraction), percentile(1.0 - fraction)]
+            }
+            ScaleMode::Log =>
+            {
+                match samples.iter().find(|&&x| x > 0.0)
+                {
+                    Some(&min) =>
+                    {
+                        let mut scale = [0.0; 33];
+                        scale.iter_mut().enumerate().for_each(|(idx, val)| {
+                            *val = min * (max / min).powf(idx as f64 / 32.0)
+                        });
+                        return scale;
+                    }
+                    //no positive values
+                    None => [min, max],
+                }
+            }
+        };
+        let mut scale = [0.0; 33];
+        scale
+            .iter_mut()
+            .enumerate()
+            .for_each(|(idx, val)| *val = min + (max - min) * idx as f64 / 32.0);
+        scale
+    }
+
+    ///maps `array` (indexed [horizontal, vertical]) to row-major RGB image, piecewise linearly between
+    ///`scale` values (see `scale()`); values outside `scale` get extreme colors
+    pub fn colorize(&self, array : ArrayView2<'_, f64>, scale : &[f64; 33]) -> Vec<u8>
+    {
+        let grad = self.raw_grad();
+        let quantile_out_width = 1.0 / 33.0;
+        let mul_add_coef : Vec<(_, _)> = scale
+            .windows(2)
+            .zip((0..32).map(|x| {
+                (
+                    x as f64 * quantile_out_width,
+                    (x + 1) as f64 * quantile_out_width,
+                )
+            }))
+            .map(|(x, (ymin, ymax))| {
+                let (xmin, xmax) = (x[0], x[1]);
+                let mul = (ymax - ymin) / (xmax - xmin);
+                let add = ymin - xmin * mul;
+                (mul, add)
+            })
+            .collect();
+        let rgb = array
+            .t()
+            .into_iter()
+            .map(|&x| {
+                let color;
+                match scale
+                    .binary_search_by(|a| a.partial_cmp(&x).unwrap_or(std::cmp::Ordering::Equal))
+                {
+                    Ok(idx) => color = grad.at(quantile_out_width * idx as f64).to_rgba8(),
+                    Err(0) => color = grad.at(0.0).to_rgba8(),
+                    Err(33) => color = grad.at(quantile_out_width * 32.0).to_rgba8(),
+                    Err(idx) =>
+                    {
+                        let (mul, add) = mul_add_coef[idx - 1];
+                        color = grad.at(f64::mul_add(x, mul, add)).to_rgba8();
+                    }
+                };
+                [color[0], color[1], color[2]]
+            })
+            .flatten()
+            .collect::<Vec<u8>>();
+        rgb
     }
-    fn gen_legend(&self, ctx : &Context) -> Option<TextureHandle>
+
+    fn gen_legend(&self, ctx : &Context) -> TextureHandle
     {
         let mut legend = self
             .raw_grad()
@@ -435,7 +1192,7 @@ impl TTGradients
                 legend[x + 8] = 0;
             });
         let legend = ColorImage::from_rgb([3, 1024], &legend);
-        Some(ctx.load_texture(self.as_ref(), legend, TextureOptions::LINEAR))
+        ctx.load_texture(self.name(), legend, TextureOptions::LINEAR)
     }
 }
 
@@ -479,13 +1236,41 @@ impl ExecutionTimeMeas
     }
 }
 //=======================================
+//================Helpers================
+//=======================================
+
+///`value` with 4 significant digits (without exponent for typical frame rates & sequence lengths)
+pub fn format_physical(value : f64, unit : &str) -> String
+{
+    let magnitude = if value == 0.0
+    {
+        0
+    }
+    else
+    {
+        value.abs().log10().floor() as i32
+    };
+    let decimals = (3 - magnitude).clamp(0, 9) as usize;
+    format!("{:.*} {}", decimals, value, unit)
+}
+
+///parses number at the beginning of `text` (eg. "50 Hz"); accepts both decimal point & comma
+pub fn parse_leading_f64(text : &str) -> Option<f64>
+{
+    let text = text.trim_start().replace(',', ".");
+    let len = text
+        .find(|c : char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+        .unwrap_or(text.len());
+    //shorten until valid (eg. trailing 'e' of unit)
+    (1..=len).rev().find_map(|len| text[..len].parse().ok())
+}
+//=======================================
 //================Statics================
 //=======================================
 
-static mut phase_grad_legend : Option<TextureHandle> = None;
-static mut linear_grad_legend : Option<TextureHandle> = None;
 lazy_static! {
-    static ref phase_grad: colorgrad::Gradient = colorgrad::CustomGradient::new()
+    static ref gradient_registry: RwLock<TTGradientRegistry> = RwLock::new(Default::default());
+    static ref phase_grad: Arc<colorgrad::Gradient> = colorgrad::CustomGradient::new()
         .colors(&[
             colorgrad::Color::new(0.975609, 0.520118, 0.970963, 1.0),
             colorgrad::Color::new(0.980325, 0.517215, 0.963189, 1.0),
@@ -746,6 +1531,119 @@ lazy_static! {
         ])
         .interpolation(colorgrad::Interpolation::Linear)
         .build()
+        .map(Arc::new)
         .unwrap();
-    static ref linear_grad: colorgrad::Gradient = colorgrad::inferno();
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    ///checks all 33 scale values against `expected(idx)`
+    fn check_scale(scale : &[f64; 33], expected : impl Fn(f64) -> f64, mode : &str)
+    {
+        for (idx, &value) in scale.iter().enumerate()
+        {
+            let expected = expected(idx as f64);
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{} scale [{}]: {} != {}",
+                mode,
+                idx,
+                value,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn scale_modes()
+    {
+        let grad = TTGradients::Linear(ColorMap::default(), false);
+        //unordered values in [-4; 28]
+        let samples : Vec<f64> = (-4..=28).rev().map(f64::from).collect();
+        let scale = |mode, range, clip| grad.scale(samples.clone(), mode, range, clip);
+        check_scale(
+            &scale(ScaleMode::Linear, [0.0; 2], 0.0),
+            |i| i - 4.0,
+            "linear",
+        );
+        //range may be given in any order
+        for mode in [ScaleMode::Fixed, ScaleMode::Linked]
+        {
+            check_scale(
+                &scale(mode, [10.0, 2.0], 0.0),
+                |i| 2.0 + i / 4.0,
+                mode.as_ref(),
+            );
+        }
+        check_scale(
+            &scale(ScaleMode::Symmetric, [0.0; 2], 0.0),
+            |i| -28.0 + i * 56.0 / 32.0,
+            "symmetric",
+        );
+        //101 values, 10% clipped on each side
+        let samples : Vec<f64> = (0..=100).map(f64::from).collect();
+        check_scale(
+            &grad.scale(samples, ScaleMode::Percentile, [0.0; 2], 10.0),
+            |i| 10.0 + i * 80.0 / 32.0,
+            "percentile",
+        );
+        //geometric between smallest positive & max value
+        let samples = vec![-1.0, 0.0, 1.0, 4.0, 256.0];
+        check_scale(
+            &grad.scale(samples, ScaleMode::Log, [0.0; 2], 0.0),
+            |i| 2f64.powf(i / 4.0),
+            "log",
+        );
+    }
+
+    #[test]
+    fn log_scale_without_positive_values_is_linear()
+    {
+        let grad = TTGradients::Linear(ColorMap::default(), false);
+        let samples = vec![-3.0, -1.0, 0.0];
+        check_scale(
+            &grad.scale(samples, ScaleMode::Log, [0.0; 2], 0.0),
+            |i| -3.0 + i * 3.0 / 32.0,
+            "log",
+        );
+    }
+
+    #[test]
+    fn load_custom_gradient()
+    {
+        let path = std::env::temp_dir().join("tt_common_custom_gradient.txt");
+        let load = |text : &str| {
+            std::fs::write(&path, text).unwrap();
+            TTGradients::load_custom(&path)
+        };
+        let custom = || -> Vec<[u8; 4]> {
+            gradient_registry
+                .read()
+                .custom
+                .iter()
+                .map(|c| c.to_rgba8())
+                .collect()
+        };
+        //"r g b" (space or comma separated) & CSS colors, empty lines are skipped
+        load("0 0 0\n\n255, 128, 0\n  orange\n#0000ff\n").unwrap();
+        let colors = [
+            [0, 0, 0, 255],
+            [255, 128, 0, 255],
+            [255, 165, 0, 255],
+            [0, 0, 255, 255],
+        ];
+        assert_eq!(custom(), colors);
+        //failed load keeps previous colors
+        let error = load("white\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("at least 2 colors"), "{}", error);
+        let error = load("white\n1 2 3\n\nnot a color\n").unwrap_err();
+        assert!(error.to_string().starts_with("line 4:"), "{}", error);
+        let error = load("256 0 0\nblack\n").unwrap_err();
+        assert!(error.to_string().starts_with("line 1:"), "{}", error);
+        assert_eq!(custom(), colors);
+    }
 }
@@ -0,0 +1,617 @@
+use image::{ImageBuffer, ImageFormat, Luma, Rgb, RgbImage};
+use ndarray::Array2;
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::tt_common::*;
+
+//=======================================
+//================Consts=================
+//=======================================
+
+const GLYPH_WIDTH : u32 = 5;
+const GLYPH_HEIGHT : u32 = 7;
+///5x7 bitmap font used for legend labels; each row- 5 LSb, MSb is leftmost pixel
+const GLYPHS : &[(char, [u8; 7])] = &[
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('e', [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E]),
+];
+const WHITE : Rgb<u8> = Rgb([255, 255, 255]);
+const BLACK : Rgb<u8> = Rgb([0, 0, 0]);
+///languages using decimal comma (ISO 639-1)
+const DECIMAL_COMMA_LANGUAGES : &[&str] = &[
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr", "gl", "hr", "hu", "id", "is",
+    "it", "lt", "lv", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr",
+    "uk", "vi",
+];
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///decimal separator used in exported text files
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum DecimalSeparator
+{
+    #[default]
+    Point,
+    ///values are delimited with ';' in csv files
+    Comma,
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl DecimalSeparator
+{
+    ///separator of current locale (LC_ALL, LC_NUMERIC or LANG environment variable), `Point` if not set
+    pub fn from_locale() -> Self
+    {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|locale| !locale.is_empty())
+            .unwrap_or_default();
+        let language = locale
+            .split(|c| c == '_' || c == '-' || c == '.')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if DECIMAL_COMMA_LANGUAGES.contains(&language.as_str())
+        {
+            DecimalSeparator::Comma
+        }
+        else
+        {
+            DecimalSeparator::Point
+        }
+    }
+}
+
+impl TTRawView
+{
+    ///`data` has to be indexed [horizontal, vertical] view axis of `view_mode`
+    pub fn new(data : Array2<f64>, view_mode : &ViewMode, settings : &GlobalSettings) -> Self
+    {
+        let axes = view_mode.get_view_axes();
+        let offset = if settings.roi_zoom.load(Relaxed)
+        {
+            axes.map(|axis| settings.get_roi(axis).start)
+        }
+        else
+        {
+            [0, 0]
+        };
+        Self { data, axes, offset }
+    }
+
+    ///labels (as displayed in GUI- scales & components are 1-based) of consecutive elements along horizontal(0) or vertical(1) axis
+    pub fn labels(&self, axis : usize) -> Vec<f64>
+    {
+        let start = self.offset[axis] + self.axes[axis].display_offset();
+        (start..start + self.data.len_of(ndarray::Axis(axis)))
+            .map(|x| x as f64)
+            .collect()
+    }
+
+    ///min & max of finite values (`None` if there are none)
+    pub fn range(&self) -> Option<[f64; 2]>
+    {
+        self.data
+            .iter()
+            .filter(|x| x.is_finite())
+            .fold(None, |range, &x| {
+                match range
+                {
+                    None => Some([x, x]),
+                    Some([min, max]) => Some([min.min(x), max.max(x)]),
+                }
+            })
+    }
+
+    ///output format is chosen by extension: ".npy", ".txt" (tab delimited) or csv otherwise;
+    ///text files use decimal separator of current locale
+    pub fn export(&self, path : &Path) -> io::Result<()>
+    {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let separator = DecimalSeparator::from_locale();
+        match extension.as_str()
+        {
+            "npy" => self.export_npy(path),
+            "txt" => self.export_text(path, separator, '\t'),
+            _ if separator == DecimalSeparator::Comma => self.export_text(path, separator, ';'),
+            _ => self.export_text(path, separator, ','),
+        }
+    }
+
+    ///table with vertical axis along rows & horizontal along columns; 1st row & column contain axis labels,
+    ///top left cell names axes (eg. "y\x")
+    pub fn export_text(
+        &self,
+        path : &Path,
+        separator : DecimalSeparator,
+        delimiter : char,
+    ) -> io::Result<()>
+    {
+        let format = |x : f64| {
+            let text = x.to_string();
+            match separator
+            {
+                DecimalSeparator::Point => text,
+                DecimalSeparator::Comma => text.replace('.', ","),
+            }
+        };
+        let mut file = BufWriter::new(File::create(path)?);
+        write!(
+            file,
+            "{}\\{}",
+            Into::<char>::into(self.axes[1]),
+            Into::<char>::into(self.axes[0])
+        )?;
+        for label in self.labels(0)
+        {
+            write!(file, "{}{}", delimiter, format(label))?;
+        }
+        writeln!(file)?;
+        for (label, row) in self.labels(1).into_iter().zip(self.data.columns())
+        {
+            write!(file, "{}", format(label))?;
+            for x in row
+            {
+                write!(file, "{}{}", delimiter, format(*x))?;
+            }
+            writeln!(file)?;
+        }
+        file.flush()
+    }
+
+    ///writes values (shape [vertical, horizontal], like image) to `path` & axis labels to
+    ///"<file stem>_<axis>.npy" files (eg. "view_x.npy", "view_y.npy")
+    pub fn export_npy(&self, path : &Path) -> io::Result<()>
+    {
+        let (h_len, v_len) = self.data.dim();
+        write_npy(path, &[v_len, h_len], self.data.t().iter().cloned())?;
+        for axis in 0..2
+        {
+            let labels = self.labels(axis);
+            write_npy(
+                &self.labels_path(path, axis),
+                &[labels.len()],
+                labels.into_iter(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn labels_path(&self, path : &Path, axis : usize) -> PathBuf
+    {
+        let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+        file_name.push(format!(
+            "_{}.npy",
+            Into::<char>::into(self.axes[axis]).to_ascii_lowercase()
+        ));
+        path.with_file_name(file_name)
+    }
+
+    ///writes `views` (eg. consecutive positions along one axis) as image stack, format is chosen by extension:
+    ///multi-page 32-bit float tiff (".tif", ".tiff") or 16-bit grayscale png frames "<file stem>_0000.png", ...
+    ///(common for all frames value range is written to "<file stem>_scale.txt")
+    pub fn export_stack(views : &[TTRawView], path : &Path) -> io::Result<()>
+    {
+        match path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("tif" | "tiff") => write_tiff_stack(views, path),
+            Some("png") => write_png_stack(views, path),
+            _ =>
+            {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "image stack can be exported only to tiff or png",
+                ))
+            }
+        }
+    }
+}
+
+impl Thermogram
+{
+    ///raw values for ".csv", ".txt" & ".npy" files (see `TTRawView::export()`), png with legend otherwise
+    pub fn export(&self, path : &Path, source : &str) -> io::Result<()>
+    {
+        match path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("csv" | "txt" | "npy") => self.raw.export(path),
+            _ => self.export_png(path, source),
+        }
+    }
+
+    ///writes thermogram with legend to png file & its params to "<file stem>_params.txt" sidecar
+    ///(distinct from ".txt" raw values export of same view)
+    pub fn export_png(&self, path : &Path, source : &str) -> io::Result<()>
+    {
+        self.legend_image()
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        fs::write(
+            path.with_file_name(format!("{}_params.txt", stem)),
+            self.description(source),
+        )
+    }
+
+    ///thermogram image with color bar & scale labels on the right
+    fn legend_image(&self) -> RgbImage
+    {
+        let [width, height] = self.pixels.size;
+        let (width, height) = (width as u32, height as u32);
+        //enlarge labels for big images
+        let sc = (height / 300).max(1);
+        //top & bottom margin, so extreme labels fit in image
+        let margin = GLYPH_HEIGHT * sc;
+        let labels : Vec<String> = self.scale.iter().map(|x| format!("{:.2}", x)).collect();
+        let label_chars = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+        let bar_x = width + 4 * sc;
+        let bar_width = 10 * sc;
+        let label_x = bar_x + bar_width + 4 * sc;
+        let out_width = label_x + label_chars * (GLYPH_WIDTH + 1) * sc + 2 * sc;
+        let out_height = height + 2 * margin;
+        let mut out = RgbImage::from_pixel(out_width, out_height, WHITE);
+
+        self.pixels.pixels.iter().enumerate().for_each(|(idx, px)| {
+            let (x, y) = (idx as u32 % width, idx as u32 / width);
+            out.put_pixel(x, y + margin, Rgb([px.r(), px.g(), px.b()]));
+        });
+
+        //color bar, max value on top (same mapping as in `TTGradients::colorize()`)
+        let y_max = (height - 1).max(1) as f64;
+        let grad = self.legend.raw_grad();
+        for y in 0..height
+        {
+            let color = grad.at((1.0 - y as f64 / y_max) * 32.0 / 33.0).to_rgba8();
+            for x in bar_x..bar_x + bar_width
+            {
+                out.put_pixel(x, y + margin, Rgb([color[0], color[1], color[2]]));
+            }
+        }
+
+        //labels with ticks
+        let label_count = height / ((GLYPH_HEIGHT + 3) * sc) + 1;
+        let stride = if label_count >= 33
+        {
+            1
+        }
+        else if label_count >= 17
+        {
+            2
+        }
+        else if label_count >= 9
+        {
+            4
+        }
+        else
+        {
+            8
+        };
+        for i in (0..33).step_by(stride)
+        {
+            let y = margin + ((1.0 - i as f64 / 32.0) * y_max) as u32;
+            for x in bar_x + bar_width..label_x - sc
+            {
+                for dy in 0..sc
+                {
+                    out.put_pixel(x, (y + dy).min(out_height - 1), BLACK);
+                }
+            }
+            draw_text(
+                &mut out,
+                &labels[i],
+                label_x,
+                y.saturating_sub(GLYPH_HEIGHT * sc / 2),
+                sc,
+            );
+        }
+        out
+    }
+
+    ///human readable description of params used to generate this thermogram
+    pub fn description(&self, source : &str) -> String
+    {
+        let view_mode = &self.view_mode;
+        let settings = &self.settings;
+        let on_off = |x : bool| if x { "on" } else { "off" };
+        let mut text = String::new();
+        let domain = view_mode.domain.load(Relaxed);
+        let [a, b] = view_mode.get_view_axes();
+        let _ = writeln!(text, "source: {}", source);
+        let _ = writeln!(text, "domain: {}", domain.as_ref());
+        let _ = writeln!(
+            text,
+            "view axes: {}-{}",
+            Into::<char>::into(a),
+            Into::<char>::into(b)
+        );
+        let position = view_mode.position.read();
+        for &axis in view_mode.get_settings_axes()
+        {
+            let pos = position[axis as usize] + axis.display_offset();
+            let _ = write!(text, "position {}: {}", Into::<char>::into(axis), pos);
+            match settings.physical_position(
+                axis,
+                position[axis as usize],
+                view_mode.wavelet.load(Relaxed),
+            )
+            {
+                Some((value, unit)) =>
+                {
+                    let _ = writeln!(text, " ({})", format_physical(value, unit));
+                }
+                None =>
+                {
+                    let _ = writeln!(text);
+                }
+            }
+        }
+        match domain
+        {
+            ViewModeDomain::TimeView =>
+            {
+                let _ = writeln!(
+                    text,
+                    "differential: {}",
+                    on_off(view_mode.differential.load(Relaxed))
+                );
+            }
+            ViewModeDomain::FourierView =>
+            {
+                let _ = writeln!(
+                    text,
+                    "display mode: {}",
+                    view_mode.display_mode.load(Relaxed).as_ref()
+                );
+            }
+            ViewModeDomain::FastWaveletView | ViewModeDomain::WaveletView =>
+            {
+                let _ = writeln!(
+                    text,
+                    "display mode: {}",
+                    view_mode.display_mode.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(
+                    text,
+                    "wavelet: {}",
+                    view_mode.wavelet.load(Relaxed).as_ref()
+                );
+            }
+            ViewModeDomain::PCTView => (),
+            ViewModeDomain::LockInView =>
+            {
+                let _ = writeln!(
+                    text,
+                    "display mode: {}",
+                    view_mode.display_mode.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(
+                    text,
+                    "lock-in method: {}",
+                    view_mode.lockin_method.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(
+                    text,
+                    "lock-in frequency [Hz]: {}",
+                    *view_mode.lockin_freq.read()
+                );
+            }
+            ViewModeDomain::TSRView =>
+            {
+                let _ = writeln!(
+                    text,
+                    "tsr mode: {}",
+                    view_mode.tsr_mode.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(text, "tsr degree: {}", view_mode.tsr_degree.load(Relaxed));
+            }
+            ViewModeDomain::CompositeWaveletView =>
+            {
+                let [s_min, s_max] = *view_mode.scale_band.read();
+                let _ = writeln!(
+                    text,
+                    "composite mode: {}",
+                    view_mode.composite_mode.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(
+                    text,
+                    "wavelet: {}",
+                    view_mode.wavelet.load(Relaxed).as_ref()
+                );
+                let _ = writeln!(text, "scales: {}-{}", s_min + 1, s_max + 1);
+            }
+        }
+        let _ = writeln!(
+            text,
+            "reference frame: {}",
+            settings.reference_frame.load(Relaxed)
+        );
+        let _ = writeln!(
+            text,
+            "diff. pre-processing: {}",
+            on_off(settings.diff_preprocess.load(Relaxed))
+        );
+        let _ = writeln!(text, "window: {}", settings.window.load(Relaxed).as_ref());
+        let _ = writeln!(text, "frame rate [Hz]: {}", *settings.frame_rate.read());
+        let _ = writeln!(text, "denoise: {}", on_off(view_mode.denoise.load(Relaxed)));
+        let _ = writeln!(
+            text,
+            "roi zoom: {}",
+            on_off(settings.roi_zoom.load(Relaxed))
+        );
+        for axis in [a, b]
+        {
+            let roi = settings.get_roi(axis);
+            let _ = writeln!(
+                text,
+                "roi {}: {}-{}",
+                Into::<char>::into(axis),
+                roi.start,
+                roi.end
+            );
+        }
+        let _ = writeln!(text, "legend: {}", self.legend.name());
+        let scale : Vec<String> = self.scale.iter().map(|x| x.to_string()).collect();
+        let _ = writeln!(text, "scale: {}", scale.join(", "));
+        text
+    }
+}
+
+//=======================================
+//================Helpers================
+//=======================================
+
+///each view is one page (image rows along vertical view axis)
+fn write_tiff_stack(views : &[TTRawView], path : &Path) -> io::Result<()>
+{
+    use tiff::encoder::{colortype::Gray32Float, TiffEncoder};
+    let to_io = |e : tiff::TiffError| io::Error::new(io::ErrorKind::Other, e);
+    let mut encoder = TiffEncoder::new(BufWriter::new(File::create(path)?)).map_err(to_io)?;
+    for view in views
+    {
+        let (width, height) = view.data.dim();
+        let values : Vec<f32> = view.data.t().iter().map(|&x| x as f32).collect();
+        encoder
+            .write_image::<Gray32Float>(width as u32, height as u32, &values)
+            .map_err(to_io)?;
+    }
+    Ok(())
+}
+
+///values are linearly mapped from [min; max] of whole stack to [0; 65535]
+fn write_png_stack(views : &[TTRawView], path : &Path) -> io::Result<()>
+{
+    let (min, max) = views
+        .iter()
+        .flat_map(|view| view.data.iter())
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &x| {
+            (min.min(x), max.max(x))
+        });
+    let (min, max) = if min <= max { (min, max) } else { (0.0, 0.0) };
+    let range = if max > min { max - min } else { 1.0 };
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    for (idx, view) in views.iter().enumerate()
+    {
+        let (width, height) = view.data.dim();
+        //float to int casts saturate (NaN is mapped to 0)
+        let pixels : Vec<u16> = view
+            .data
+            .t()
+            .iter()
+            .map(|&x| ((x - min) / range * u16::MAX as f64).round() as u16)
+            .collect();
+        //buffer always matches view size
+        let image : ImageBuffer<Luma<u16>, _> =
+            ImageBuffer::from_raw(width as u32, height as u32, pixels).unwrap();
+        image
+            .save_with_format(
+                path.with_file_name(format!("{}_{:04}.png", stem, idx)),
+                ImageFormat::Png,
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    }
+    fs::write(
+        path.with_file_name(format!("{}_scale.txt", stem)),
+        format!("min: {}\nmax: {}\n", min, max),
+    )
+}
+
+///writes little endian f64 values in NumPy .npy (version 1.0) format, C order
+fn write_npy(path : &Path, shape : &[usize], values : impl Iterator<Item = f64>) -> io::Result<()>
+{
+    let shape = match shape
+    {
+        [len] => format!("{},", len),
+        _ =>
+        {
+            shape
+                .iter()
+                .map(|len| len.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}), }}",
+        shape
+    );
+    //magic(6) + version(2) + header length(2) + header + '\n' has to be 64 bytes aligned
+    let padding = (64 - (10 + header.len() + 1) % 64) % 64;
+    header.extend(std::iter::repeat(' ').take(padding));
+    header.push('\n');
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"\x93NUMPY\x01\x00")?;
+    file.write_all(&(header.len() as u16).to_le_bytes())?;
+    file.write_all(header.as_bytes())?;
+    for x in values
+    {
+        file.write_all(&x.to_le_bytes())?;
+    }
+    file.flush()
+}
+
+///draws `text` (only chars defined in `GLYPHS`, others are left blank) with top left corner at (x,y)
+fn draw_text(image : &mut RgbImage, text : &str, x : u32, y : u32, sc : u32)
+{
+    let (width, height) = image.dimensions();
+    text.chars().enumerate().for_each(|(char_idx, c)| {
+        if let Some((_, rows)) = GLYPHS.iter().find(|(glyph, _)| *glyph == c)
+        {
+            let char_x = x + char_idx as u32 * (GLYPH_WIDTH + 1) * sc;
+            for (row_idx, row) in rows.iter().enumerate()
+            {
+                for col_idx in 0..GLYPH_WIDTH
+                {
+                    if row & (0x10 >> col_idx) != 0
+                    {
+                        for dy in 0..sc
+                        {
+                            for dx in 0..sc
+                            {
+                                let px = char_x + col_idx * sc + dx;
+                                let py = y + row_idx as u32 * sc + dy;
+                                if px < width && py < height
+                                {
+                                    image.put_pixel(px, py, BLACK);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
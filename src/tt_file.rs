@@ -2,29 +2,35 @@ use array_base_wrapper as abw;
 use binrw::io::{BufReader, NoSeek};
 use binrw::*;
 use fast_float::FastFloatParser;
-use ndarray::{Array, Array3, ArrayBase, Dimension, Ix3, OwnedRepr, Zip};
+use ndarray::{Array, Array3, ArrayBase, Dimension, Ix3, OwnedRepr};
 use ndarray::{Data, Dim};
+use rayon::prelude::*;
 use rfd::FileDialog;
 use std::ffi::OsString;
-#[cfg(not(debug_assertions))]
-use std::fs::remove_file;
-use std::fs::File;
-use std::io::{BufRead, BufWriter, Read, Seek, Write};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Cursor, Read, Seek, Write};
 use std::mem::{self, transmute};
-use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::ops::{Deref, DerefMut, Range};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::macros::array_pows_2_3;
 use crate::tt_common::*;
+use crate::tt_flir::{fff_from_jpeg, read_fff_sequence, FFFFrame};
 
 #[derive(Clone, PartialEq)]
 enum TTFileType
 {
     TTCF,
     Text,
+    Tiff,
+    ///FLIR sequence
+    Seq,
+    ///directory of numbered png/tiff/radiometric jpeg frames
+    ImageDirectory,
     Unknown,
 }
 #[derive(Clone)]
@@ -35,348 +41,996 @@ pub struct TTFile
 }
 /// txt file with data written in human readable format, output from THERM app
 /// tttf is same as txt, only extension was change for file assosication
-/// ttcf compressed(zstd)& optimized(modified f32 instead f64) data written in binary format
+/// ttcf compressed(zstd)& optimized(22-bit lossy f32, f32 or f64; see `TTCFPrecision`) data written in binary format
+/// tiff multi-page grayscale image (8/16/32-bit integer or float), each page is one frame
+/// seq FLIR sequence of FFF records with raw sensor counts (converted to temperature [°C], see `tt_flir`)
+/// png (8/16-bit grayscale), tiff & FLIR radiometric jpeg are also accepted as frames in directory
+/// (ordered by number in file name)
 const EXTENSIONS_TTCF : &[&str] = &["ttcf"];
 const EXTENSIONS_TCTS : &[&str] = &["txt", "tcts"];
-const SUPPORTED_FILE_EXTENSIONS : &[&[&str]] = &[EXTENSIONS_TTCF, EXTENSIONS_TCTS];
+const EXTENSIONS_TIFF : &[&str] = &["tif", "tiff"];
+const EXTENSIONS_SEQ : &[&str] = &["seq"];
+const EXTENSIONS_PNG : &[&str] = &["png"];
+const EXTENSIONS_JPEG : &[&str] = &["jpg", "jpeg"];
+const SUPPORTED_FILE_EXTENSIONS : &[&[&str]] = &[
+    EXTENSIONS_TTCF,
+    EXTENSIONS_TCTS,
+    EXTENSIONS_TIFF,
+    EXTENSIONS_SEQ,
+];
+const FRAME_FILE_EXTENSIONS : &[&[&str]] = &[EXTENSIONS_PNG, EXTENSIONS_TIFF, EXTENSIONS_JPEG];
 const HEADER_TTCF_V1 : &str = "TTCF v1";
+/// v2: zstd compressed sequence of chunks (`ChunkHeader` + payload), terminated with `CHUNK_END`;
+/// chunks with unknown id are skipped, so new chunks can be added without breaking older readers
+const HEADER_TTCF_V2 : &str = "TTCF v2";
+const CHUNK_DIMENSIONS : [u8; 4] = *b"DIMS";
+const CHUNK_METADATA : [u8; 4] = *b"META";
+const CHUNK_SOURCE : [u8; 4] = *b"SRC ";
+///must follow `CHUNK_DIMENSIONS`
+const CHUNK_DATA : [u8; 4] = *b"DATA";
+const CHUNK_END : [u8; 4] = *b"END ";
+///id & payload length
+const CHUNK_HEADER_LEN : usize = 12;
+///number of values converted at once when writing/reading data chunk
+const DATA_BLOCK_LEN : usize = 1 << 16;
+///number of values of loaded frames transposed at once (64 MiB)
+const TRANSPOSE_BLOCK_LEN : usize = 1 << 23;
+///bytes of text file parsed at once (only complete frames are parsed, rest is carried to next block)
+const TEXT_BLOCK_LEN : usize = 1 << 26;
 
-macro_rules! ferror {
-    ($file_state:ident) => {
-        let _ = $file_state.compare_exchange(
-            FileState::Loading,
-            FileState::Error,
-            Ordering::SeqCst,
-            Ordering::Acquire,
-        );
-        return None;
-    };
+///reason why input file could not be loaded
+#[derive(Debug)]
+pub enum TTLoadError
+{
+    Io(io::Error),
+    ///extension of file (if it has any)
+    UnsupportedExtension(Option<String>),
+    ///unknown ttcf header/version
+    BadHeader(String),
+    Zstd(io::Error),
+    Parse(binrw::Error),
+    ///ttcf structure is invalid (eg. missing chunk)
+    Corrupted(&'static str),
+    ///`line` & `frame` are 1-based
+    InconsistentColumns
+    {
+        line :     usize,
+        frame :    usize,
+        expected : usize,
+        found :    usize,
+    },
+    ///`frame` is 1-based
+    InconsistentRows
+    {
+        frame :    usize,
+        expected : usize,
+        found :    usize,
+    },
+    TooFewFrames(usize),
+    TooSmallImage
+    {
+        width :  usize,
+        height : usize,
+    },
+    Image(image::ImageError),
+    Tiff(tiff::TiffError),
+    ///only single channel (grayscale) images are supported
+    UnsupportedPixelFormat(String),
+    ///invalid FLIR seq/radiometric jpeg
+    Flir(&'static str),
+    ///`frame` is 1-based; sizes are [width, height]
+    InconsistentImageSize
+    {
+        frame :    usize,
+        expected : [usize; 2],
+        found :    [usize; 2],
+    },
+    ///loading was cancelled- file selection changed or app is closing (not an error of file itself)
+    Outdated,
 }
-
-impl TTFile
+impl fmt::Display for TTLoadError
 {
-    //////////////
-    //
-    //////////////
-    pub fn data_store(&self, data : &TTInputData) -> Result<(), ()>
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        let mut new_file = self.path.clone();
-        if self.file_type != TTFileType::TTCF
+        match self
         {
-            new_file.set_extension("ttcf");
-            if let Ok(f) = File::create(new_file)
+            TTLoadError::Io(e) => write!(f, "I/O error: {}", e),
+            TTLoadError::UnsupportedExtension(Some(ext)) =>
             {
-                let mut fr = BufWriter::new(f);
-                let mut header : [u8; 20] = [0; 20];
-                header
-                    .as_mut_slice()
-                    .write_all(HEADER_TTCF_V1.as_bytes())
-                    .expect("");
-                if let Err(_) = fr.write_all(&header)
-                {
-                    return Err(());
-                }
-                let mut encoder = zstd::Encoder::new(fr, 8).unwrap();
-                let _ = encoder.include_checksum(true);
-                let _ = encoder.multithread(num_cpus::get().saturating_sub(2) as u32);
-                let encoder = encoder.auto_finish();
-
-                let mut encoder_buffered = NoSeek::new(BufWriter::new(encoder));
-                if let Ok(_) = FileTTCF::from(data).write_le(&mut encoder_buffered)
-                {
-                    #[cfg(not(debug_assertions))]
-                    let _ = remove_file(self.path.clone());
-                    return Ok(());
-                }
-                else
-                {
-                    return Err(());
-                }
+                write!(f, "unsupported file extension \"{}\"", ext)
             }
-            else
+            TTLoadError::UnsupportedExtension(None) => write!(f, "file has no extension"),
+            TTLoadError::BadHeader(header) => write!(f, "unknown ttcf header \"{}\"", header),
+            TTLoadError::Zstd(e) => write!(f, "decompression failed: {}", e),
+            TTLoadError::Parse(e) => write!(f, "ttcf parse error: {}", e),
+            TTLoadError::Corrupted(reason) => write!(f, "corrupted ttcf file: {}", reason),
+            TTLoadError::InconsistentColumns {
+                line,
+                frame,
+                expected,
+                found,
+            } =>
+            {
+                write!(
+                    f,
+                    "line {} (frame {}): {} columns, expected {}",
+                    line, frame, found, expected
+                )
+            }
+            TTLoadError::InconsistentRows {
+                frame,
+                expected,
+                found,
+            } => write!(f, "frame {}: {} rows, expected {}", frame, found, expected),
+            TTLoadError::TooFewFrames(frames) =>
             {
-                return Err(());
+                write!(f, "{} frame(s) found, at least 2 required", frames)
             }
+            TTLoadError::TooSmallImage { width, height } =>
+            {
+                write!(
+                    f,
+                    "image {}x{} px is too small (at least 2x2 required)",
+                    width, height
+                )
+            }
+            TTLoadError::Image(e) => write!(f, "image error: {}", e),
+            TTLoadError::Tiff(e) => write!(f, "tiff error: {}", e),
+            TTLoadError::Flir(reason) => write!(f, "invalid FLIR file: {}", reason),
+            TTLoadError::UnsupportedPixelFormat(format) =>
+            {
+                write!(
+                    f,
+                    "unsupported pixel format {} (grayscale expected)",
+                    format
+                )
+            }
+            TTLoadError::InconsistentImageSize {
+                frame,
+                expected,
+                found,
+            } =>
+            {
+                write!(
+                    f,
+                    "frame {}: image {}x{} px, expected {}x{} px",
+                    frame, found[0], found[1], expected[0], expected[1]
+                )
+            }
+            TTLoadError::Outdated => write!(f, "loading was cancelled"),
+        }
+    }
+}
+impl std::error::Error for TTLoadError {}
+impl From<io::Error> for TTLoadError
+{
+    fn from(value : io::Error) -> Self { TTLoadError::Io(value) }
+}
+impl From<binrw::Error> for TTLoadError
+{
+    ///binrw reads only from decompressed stream, so its I/O errors come from decoder
+    fn from(value : binrw::Error) -> Self
+    {
+        match value
+        {
+            binrw::Error::Io(e) => TTLoadError::Zstd(e),
+            e => TTLoadError::Parse(e),
+        }
+    }
+}
+impl From<image::ImageError> for TTLoadError
+{
+    fn from(value : image::ImageError) -> Self { TTLoadError::Image(value) }
+}
+impl From<tiff::TiffError> for TTLoadError
+{
+    fn from(value : tiff::TiffError) -> Self { TTLoadError::Tiff(value) }
+}
+
+///frames (row-major images of equal size) collected while loading; frames are transposed in blocks
+///directly into time contiguous (X, Y, T) layout of `TTInputData`, so sequence is never held twice in memory
+#[derive(Default)]
+struct FrameStack
+{
+    width :           usize,
+    height :          usize,
+    ///pushed frames (including `pending` ones)
+    frames :          usize,
+    ///expected count of frames (used for allocation of `values`)
+    expected_frames : usize,
+    ///length of time axis in `values` (zero padded)
+    padded :          usize,
+    ///(X, Y, T) layout
+    values :          Vec<f64>,
+    ///frames waiting for transposition, (T, Y, X) layout
+    pending :         Vec<f64>,
+    ///header entries & per frame timestamps (see `TTMetadata::new()`)
+    entries :         Vec<(String, String)>,
+    timestamps :      Vec<f64>,
+}
+impl FrameStack
+{
+    ///`size` is [width, height] of frame
+    fn push(&mut self, size : [usize; 2], values : &[f64]) -> Result<(), TTLoadError>
+    {
+        if self.frames == 0
+        {
+            [self.width, self.height] = size;
+        }
+        else if size != [self.width, self.height]
+        {
+            return Err(TTLoadError::InconsistentImageSize {
+                frame :    self.frames + 1,
+                expected : [self.width, self.height],
+                found :    size,
+            });
+        }
+        if values.len() != size[0] * size[1]
+        {
+            return Err(TTLoadError::UnsupportedPixelFormat(format!(
+                "with {} samples per {}x{} px image",
+                values.len(),
+                size[0],
+                size[1]
+            )));
+        }
+        self.pending.extend_from_slice(values);
+        self.frames += 1;
+        if self.pending.len() >= TRANSPOSE_BLOCK_LEN
+        {
+            self.flush();
+        }
+        Ok(())
+    }
+    ///metadata is taken from first frame
+    fn push_fff(&mut self, frame : FFFFrame) -> Result<(), TTLoadError>
+    {
+        if self.frames == 0
+        {
+            self.entries = frame.entries();
+        }
+        self.timestamps.extend(frame.time);
+        self.push([frame.width, frame.height], &frame.temperatures)
+    }
+    ///transposes `pending` frames into `values`
+    fn flush(&mut self)
+    {
+        let frame_len = self.width * self.height;
+        if frame_len == 0 || self.pending.is_empty()
+        {
+            return;
+        }
+        let count = self.pending.len() / frame_len;
+        let first = self.frames - count;
+        if self.values.is_empty()
+        {
+            self.padded = find_next_pows_2_3(self.expected_frames.max(self.frames).max(2));
+            self.values = vec![0.0; frame_len * self.padded];
+        }
+        else if self.frames > self.padded
+        {
+            //more frames than expected; leave some margin to avoid repeated relayout
+            self.set_padded(find_next_pows_2_3(self.frames + self.frames / 4), first);
+        }
+        let (width, height) = (self.width, self.height);
+        let pending = &self.pending;
+        self.values
+            .par_chunks_mut(self.padded)
+            .enumerate()
+            .for_each(|(lane, values)| {
+                //lane is time series of pixel (x, y), lane = x * height + y
+                let pixel = (lane % height) * width + lane / height;
+                values[first..first + count]
+                    .iter_mut()
+                    .zip(pending[pixel..].iter().step_by(frame_len))
+                    .for_each(|(o, &i)| *o = i);
+            });
+        self.pending.clear();
+    }
+    ///changes length of time axis in place (`stored` frames are kept, rest is zeroed)
+    fn set_padded(&mut self, padded : usize, stored : usize)
+    {
+        let (old, lanes) = (self.padded, self.width * self.height);
+        let move_lane = |values : &mut Vec<f64>, lane : usize| {
+            values.copy_within(lane * old..lane * old + stored, lane * padded);
+            values[lane * padded + stored..(lane + 1) * padded].fill(0.0);
+        };
+        if padded > old
+        {
+            //exact, as margin was already added by caller
+            self.values.reserve_exact(lanes * (padded - old));
+            self.values.resize(lanes * padded, 0.0);
+            //lanes are moved towards end, so start from last one (none is overwritten before being moved)
+            (0..lanes)
+                .rev()
+                .for_each(|lane| move_lane(&mut self.values, lane));
         }
         else
         {
-            return Ok(());
+            (0..lanes).for_each(|lane| move_lane(&mut self.values, lane));
+            self.values.truncate(lanes * padded);
+            self.values.shrink_to_fit();
+        }
+        self.padded = padded;
+    }
+    ///validates size & zero pads frames to length efficient for fft
+    fn into_input_data(mut self) -> Result<TTInputData, TTLoadError>
+    {
+        let (frames, rows, columns) = (self.frames, self.height, self.width);
+        if self.timestamps.len() != frames
+        {
+            //timestamps are missing for some frames
+            self.timestamps.clear();
+        }
+        if frames < 2
+        {
+            return Err(TTLoadError::TooFewFrames(frames));
         }
+        if rows < 2 || columns < 2
+        {
+            return Err(TTLoadError::TooSmallImage {
+                width :  columns,
+                height : rows,
+            });
+        }
+        self.flush();
+        let rounded_frames = find_next_pows_2_3(frames);
+        if rounded_frames != self.padded
+        {
+            self.set_padded(rounded_frames, frames);
+        }
+        //dimensions were validated while pushing frames
+        let data = Array::from_shape_vec((columns, rows, rounded_frames), self.values).unwrap();
+        Ok(TTInputData {
+            data,
+            frames,
+            width : columns,
+            height : rows,
+            metadata : TTMetadata::new(self.entries, self.timestamps),
+        })
+    }
+}
+
+///reader publishing fraction of `total` bytes, that have been read, to `progress`
+struct ProgressReader<'a, R>
+{
+    inner :    R,
+    read :     u64,
+    total :    u64,
+    progress : &'a TTProgress,
+}
+impl<'a, R> ProgressReader<'a, R>
+{
+    fn new(inner : R, total : u64, progress : &'a TTProgress) -> Self
+    {
+        progress.set(0.0);
+        Self {
+            inner,
+            read : 0,
+            total : total.max(1),
+            progress,
+        }
+    }
+}
+impl<R : Read> Read for ProgressReader<'_, R>
+{
+    fn read(&mut self, buf : &mut [u8]) -> io::Result<usize>
+    {
+        let len = self.inner.read(buf)?;
+        self.read += len as u64;
+        self.progress.set(self.read as f32 / self.total as f32);
+        Ok(len)
+    }
+}
+impl<R : Seek> Seek for ProgressReader<'_, R>
+{
+    fn seek(&mut self, pos : io::SeekFrom) -> io::Result<u64>
+    {
+        self.read = self.inner.seek(pos)?;
+        self.progress.set(self.read as f32 / self.total as f32);
+        Ok(self.read)
     }
+}
+
+///frame of text file: (sub)header lines, column ids & data rows (each starting with row id)
+struct TextFrame
+{
+    ///byte range in parsed block
+    range :      Range<usize>,
+    ///1-based line number (in file) of first line
+    first_line : usize,
+}
+#[derive(Default)]
+struct ParsedTextFrame
+{
+    ///false for text without column ids (eg. trailing header)
+    is_frame :        bool,
+    values :          Vec<f64>,
+    rows :            usize,
+    ///columns of first data row
+    columns :         Option<usize>,
+    first_data_line : usize,
+    ///(line, expected, found) of first row with other columns count than first row
+    column_error :    Option<(usize, usize, usize)>,
+    ///header entries (first occurrence of each key)
+    entries :         Vec<(String, String)>,
+    ///first valid timestamp in header
+    timestamp :       Option<f64>,
+}
+///classified line of text file
+enum TextLine<'a>
+{
+    ///empty or not valid UTF-8 (skipped)
+    Empty,
+    ///starting with number (column ids or data row); trimmed
+    Numeric(&'a str),
+    ///trimmed
+    Header(&'a str),
+}
 
+impl TTFile
+{
     //////////////
     //
     //////////////
-    pub fn data_load(&mut self, file_state : Arc<AtomicFileState>) -> Option<TTInputData>
+    ///converts file to ttcf (written atomically through temp file & verified by reading it back);
+    ///source file is handled according to `params.source_policy` only after successful verification
+    pub fn data_store(&self, data : &TTInputData, params : &TTStoreParams) -> io::Result<()>
     {
-        if let Ok(f) = File::open(self.path.clone())
+        if self.file_type == TTFileType::TTCF
+        {
+            return Ok(());
+        }
+        let new_file = self.path.with_extension("ttcf");
+        let temp_file = self.path.with_extension("ttcf.tmp");
+        let stored = self
+            .write_ttcf_file(&temp_file, data, params.precision)
+            .and_then(|_| Self::verify_ttcf_file(&temp_file, data, params.precision))
+            .and_then(|_| fs::rename(&temp_file, &new_file));
+        if let Err(error) = stored
+        {
+            let _ = fs::remove_file(&temp_file);
+            return Err(error);
+        }
+        match params.source_policy
         {
-            match self.path.extension()
+            SourcePolicy::Keep => Ok(()),
+            SourcePolicy::Delete =>
             {
-                Some(ext)
-                    if EXTENSIONS_TTCF.contains(&ext.to_string_lossy().to_string().as_str()) =>
+                for file in self.source_files()?
                 {
-                    self.file_type = TTFileType::TTCF;
-                    return Self::data_load_ttcf(f, file_state);
+                    fs::remove_file(file)?;
                 }
-                Some(ext)
-                    if EXTENSIONS_TCTS.contains(&ext.to_string_lossy().to_string().as_str()) =>
+                if self.file_type == TTFileType::ImageDirectory
+                {
+                    //directory is removed only if it contained nothing but frames
+                    let _ = fs::remove_dir(&self.path);
+                }
+                Ok(())
+            }
+            SourcePolicy::Archive =>
+            {
+                let dir = self
+                    .path
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(&params.archive_dir);
+                fs::create_dir_all(&dir)?;
+                let archived = dir.join(self.path.file_name().unwrap_or_default());
+                if self.file_type == TTFileType::ImageDirectory
                 {
-                    self.file_type = TTFileType::Text;
-                    return Self::data_load_tcts(f, file_state);
+                    //only frames are archived; other files stay in the directory
+                    fs::create_dir_all(&archived)?;
+                    for file in self.source_files()?
+                    {
+                        move_file(&file, &archived.join(file.file_name().unwrap_or_default()))?;
+                    }
+                    //directory is removed only if it contained nothing but frames
+                    let _ = fs::remove_dir(&self.path);
+                    Ok(())
                 }
-                Some(_) | None =>
+                else
                 {
-                    self.file_type = TTFileType::Unknown;
-                    ferror!(file_state);
+                    move_file(&self.path, &archived)
                 }
             }
         }
+    }
+    ///files from which data was loaded (frames for image directory)
+    fn source_files(&self) -> io::Result<Vec<PathBuf>>
+    {
+        if self.file_type == TTFileType::ImageDirectory
+        {
+            frame_files(&self.path)
+        }
+        else
+        {
+            Ok(vec![self.path.clone()])
+        }
+    }
+    fn write_ttcf_file(
+        &self,
+        path : &Path,
+        data : &TTInputData,
+        precision : TTCFPrecision,
+    ) -> io::Result<()>
+    {
+        let mut fr = BufWriter::new(File::create(path)?);
+        let mut header : [u8; 20] = [0; 20];
+        header
+            .as_mut_slice()
+            .write_all(HEADER_TTCF_V2.as_bytes())
+            .expect("");
+        fr.write_all(&header)?;
+        let mut encoder = zstd::Encoder::new(fr, 8)?;
+        let _ = encoder.include_checksum(true);
+        let _ = encoder.multithread(num_cpus::get().saturating_sub(2) as u32);
+        self.write_ttcf_v2(&mut BufWriter::new(&mut encoder), data, precision)?;
+        let file = encoder.finish()?.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()
+    }
+    ///checks that file at `path` contains `data` (as stored with `precision`)
+    fn verify_ttcf_file(
+        path : &Path,
+        data : &TTInputData,
+        precision : TTCFPrecision,
+    ) -> io::Result<()>
+    {
+        let mut f = File::open(path)?;
+        let mut header : [u8; 20] = [0u8; 20];
+        f.read_exact(&mut header)?;
+        let stored =
+            Self::read_ttcf_v2(BufReader::new(zstd::Decoder::new(f)?)).map_err(invalid_data)?;
+        let valid = stored.frames == data.frames
+            && stored.width == data.width
+            && stored.height == data.height
+            && stored.metadata.entries == data.metadata.entries
+            && stored.metadata.timestamps == data.metadata.timestamps
+            && stored.data.dim() == data.data.dim()
+            && stored
+                .data
+                .iter()
+                .zip(data.data.iter())
+                .all(|(stored, &x)| stored.to_bits() == precision.round_trip(x).to_bits());
+        if valid
+        {
+            Ok(())
+        }
         else
         {
-            ferror!(file_state);
+            Err(invalid_data("verification of written ttcf file failed"))
         }
     }
 
     //////////////
     //
     //////////////
-    fn data_load_tcts(f : File, file_state : Arc<AtomicFileState>) -> Option<TTInputData>
-    {
-        // let mut exec_time = ExecutionTimeMeas::new("exec_time_input.txt");
-        // exec_time.start();
-
-        //prealocate vector that will store input data
-        let mut v_f64 : Vec<f64>;
-        let estimated_capacity;
-        let mut rounded_frames;
-        if let Ok(meta) = f.metadata()
-        {
-            const FILE_HEADER_LEN : usize = 20;
-            const IMAGE_TOP_HEADER_LEN : usize = 1428 + 38 + 2;
-            const IMAGE_ROW_HEADER_LEN : usize = 1330;
-            const IMAGE_HEIGHT : usize = 288;
-            const IMAGE_WIDTH : usize = 384;
-            const IMAGE_PIXELS : usize = IMAGE_HEIGHT * IMAGE_WIDTH;
-            const IMAGE_DATA_LEN : usize = IMAGE_PIXELS * 6;
-            const IMAGE_LEN : usize = IMAGE_DATA_LEN + IMAGE_TOP_HEADER_LEN + IMAGE_ROW_HEADER_LEN;
-            let estimated_frames = (meta.len() as usize - FILE_HEADER_LEN) / IMAGE_LEN;
-            rounded_frames = find_next_pows_2_3(estimated_frames);
-            // rounded_frames = estimated_frames;
-            // exec_time.stop_print("pows_2_3 find");
-            // exec_time.start();
-
-            estimated_capacity = (rounded_frames + 1) * IMAGE_PIXELS;
-            v_f64 = Vec::with_capacity(estimated_capacity);
+    ///on error (except `TTLoadError::Outdated`) `file_state` is changed from `Loading` to `Error`
+    ///fraction of file, that has been read, is published to `progress`;
+    ///loading is aborted with `TTLoadError::Outdated` as soon as `cancel` is cancelled
+    pub fn data_load(
+        &mut self,
+        file_state : Arc<AtomicFileState>,
+        cancel : &TTCancelToken,
+        progress : &TTProgress,
+    ) -> Result<TTInputData, TTLoadError>
+    {
+        let result = self.data_load_file(cancel, progress);
+        if let Err(ref error) = result
+        {
+            if !matches!(error, TTLoadError::Outdated)
+            {
+                let _ = file_state.compare_exchange(
+                    FileState::Loading,
+                    FileState::Error,
+                    Ordering::SeqCst,
+                    Ordering::Acquire,
+                );
+            }
         }
-        else
+        result
+    }
+    fn data_load_file(
+        &mut self,
+        cancel : &TTCancelToken,
+        progress : &TTProgress,
+    ) -> Result<TTInputData, TTLoadError>
+    {
+        if self.path.is_dir()
         {
-            rounded_frames = 0;
-            estimated_capacity = 0;
-            v_f64 = Vec::default();
+            self.file_type = TTFileType::ImageDirectory;
+            return Self::data_load_image_directory(&self.path, cancel, progress);
         }
-        // exec_time.stop_print("buffer alloc");
-        // exec_time.start();
-
-        let mut fr = BufReader::new(f);
-
-        let mut not_parsing_header : bool = true;
-        let mut columns : usize = 0;
-        let mut rows : usize = 0;
-        let mut frames : usize = 0;
-
-        //find correct localization/ Is decimal point ',' or '.' ?
-        let ff_parser;
-        if let Ok(buf) = fr.fill_buf()
+        let f = File::open(self.path.clone())?;
+        let file_len = f.metadata().map_or(0, |meta| meta.len());
+        let f = ProgressReader::new(f, file_len, progress);
+        match self.path.extension()
         {
-            if buf.contains(&b',')
+            _ if has_extension(&self.path, EXTENSIONS_TTCF) =>
             {
-                ff_parser = FastFloatParser::<f64>::new(b',');
+                self.file_type = TTFileType::TTCF;
+                Self::data_load_ttcf(f)
             }
-            else
+            _ if has_extension(&self.path, EXTENSIONS_TCTS) =>
             {
-                ff_parser = FastFloatParser::<f64>::new(b'.');
+                self.file_type = TTFileType::Text;
+                Self::data_load_tcts(f, file_len as usize, cancel, TEXT_BLOCK_LEN)
+            }
+            _ if has_extension(&self.path, EXTENSIONS_TIFF) =>
+            {
+                self.file_type = TTFileType::Tiff;
+                let mut stack = FrameStack::default();
+                read_tiff(BufReader::new(f), &mut stack, true, cancel)?;
+                stack.into_input_data()
+            }
+            _ if has_extension(&self.path, EXTENSIONS_SEQ) =>
+            {
+                self.file_type = TTFileType::Seq;
+                let mut stack = FrameStack::default();
+                read_fff_sequence(BufReader::new(f), |frame| {
+                    if cancel.is_cancelled()
+                    {
+                        //file selection has been changed or app is closing, ongoing file reading is outdated
+                        return Err(TTLoadError::Outdated);
+                    }
+                    stack.push_fff(frame)
+                })?;
+                stack.into_input_data()
+            }
+            ext =>
+            {
+                self.file_type = TTFileType::Unknown;
+                Err(TTLoadError::UnsupportedExtension(
+                    ext.map(|ext| ext.to_string_lossy().to_string()),
+                ))
             }
         }
-        else
-        {
-            //error during reading from file
-            ferror!(file_state);
-        }
-        // exec_time.stop_print("localisation find");
-        // exec_time.start();
+    }
 
-        //iter file and parse it float vec
-        for line in fr.lines()
+    //////////////
+    //
+    //////////////
+    ///file is read in blocks; complete frames of each block are parsed in parallel (see `split_text_frames()`),
+    ///incomplete last frame is carried over to next block; `block_len`- bytes read at once (see `TEXT_BLOCK_LEN`)
+    fn data_load_tcts<R : Read>(
+        mut f : R,
+        file_len : usize,
+        cancel : &TTCancelToken,
+        block_len : usize,
+    ) -> Result<TTInputData, TTLoadError>
+    {
+        let mut stack = FrameStack::default();
+        let mut buffer : Vec<u8> = Vec::new();
+        //bytes & lines of file before `buffer`
+        let mut buffer_offset : usize = 0;
+        let mut buffer_line : usize = 1;
+        let mut separator : Option<u8> = None;
+        let mut frames : usize = 0;
+        //columns of first row & rows of first frame; all others have to match
+        let mut expected_columns : Option<usize> = None;
+        let mut expected_rows : Option<usize> = None;
+        //header entries (first occurrence of each key) & per frame timestamps
+        let mut entries : Vec<(String, String)> = Vec::new();
+        let mut timestamps : Vec<f64> = Vec::new();
+        let mut eof = false;
+        while !eof
         {
-            if file_state.load(Ordering::Relaxed) != FileState::Loading
+            if cancel.is_cancelled()
             {
-                //file selection has been changed, ongoing file reading is outdated/invalid
-                return None;
+                //file selection has been changed or app is closing, ongoing file reading is outdated
+                return Err(TTLoadError::Outdated);
             }
-            match line
+            let len = buffer.len();
+            buffer.resize(len + block_len, 0);
+            let read = read_block(&mut f, &mut buffer[len..])?;
+            buffer.truncate(len + read);
+            eof = read < block_len;
+
+            //find correct localization/ Is decimal point ',' or '.' ?
+            let separator = *separator.get_or_insert_with(|| {
+                if buffer[..buffer.len().min(8192)].contains(&b',')
+                {
+                    b','
+                }
+                else
+                {
+                    b'.'
+                }
+            });
+            let (text_frames, next_start, next_line) =
+                split_text_frames(&buffer, buffer_line, separator, eof);
+            let parsed : Vec<ParsedTextFrame> = text_frames
+                .par_iter()
+                .map(|frame| {
+                    parse_text_frame(&buffer[frame.range.clone()], frame.first_line, separator)
+                })
+                .collect();
+
+            for (text_frame, parsed) in text_frames.iter().zip(parsed)
             {
-                Ok(iline) =>
+                for (key, value) in parsed.entries
                 {
-                    if !iline.is_empty()
+                    if !entries.iter().any(|(k, _)| *k == key)
                     {
-                        let mut v = iline.trim_start();
-                        //unwrap will not panic, we already checked that line is not empty
-                        if let Ok((_row_id, parsed_bytes)) = ff_parser.parse_partial(v)
-                        {
-                            //if first string in linie is number
-
-                            if not_parsing_header
-                            {
-                                //parsing data
-                                rows += 1;
-                                columns = 0;
-                                v = v.get(parsed_bytes..).unwrap();
-                                loop
-                                {
-                                    v = v.trim_start();
-                                    match ff_parser.parse_partial(v)
-                                    {
-                                        Ok((val, parsed_bytes)) =>
-                                        {
-                                            columns += 1;
-                                            v_f64.push(val);
-                                            v = v.get(parsed_bytes..).unwrap();
-                                        }
-                                        Err(_) =>
-                                        {
-                                            break;
-                                        }
-                                    };
-                                }
-                            }
-                            else
-                            {
-                                //first linie with numbers are column id's; skip it
-                                not_parsing_header = true;
-                                columns = 0;
-                                rows = 0;
-                                frames += 1;
-                            }
-                        }
-                        else
-                        {
-                            //line belongs to header/subheader; skip it
-                            not_parsing_header = false;
-                        }
+                        entries.push((key, value));
+                    }
+                }
+                if !parsed.is_frame
+                {
+                    continue;
+                }
+                frames += 1;
+                if let Some((line, expected, found)) = parsed.column_error
+                {
+                    return Err(TTLoadError::InconsistentColumns {
+                        line,
+                        frame : frames,
+                        expected,
+                        found,
+                    });
+                }
+                match (expected_columns, parsed.columns)
+                {
+                    (None, columns) => expected_columns = columns,
+                    (Some(expected), Some(found)) if expected != found =>
+                    {
+                        return Err(TTLoadError::InconsistentColumns {
+                            line : parsed.first_data_line,
+                            frame : frames,
+                            expected,
+                            found,
+                        });
                     }
-                    else
+                    _ => (),
+                }
+                check_rows(&mut expected_rows, frames, parsed.rows)?;
+                //timestamps are kept only as long as every frame has one
+                if let Some(time) = parsed.timestamp
+                {
+                    if timestamps.len() + 1 == frames
                     {
-                        continue;
+                        timestamps.push(time);
                     }
                 }
-                Err(_) => continue,
-            };
+                if frames == 1
+                {
+                    //first frame parsed
+                    let bytes = buffer_offset + text_frame.range.end;
+                    stack.expected_frames = file_len / bytes.max(1) + 1;
+                }
+                stack.push([expected_columns.unwrap_or(0), parsed.rows], &parsed.values)?;
+            }
+            buffer.drain(..next_start);
+            buffer_offset += next_start;
+            buffer_line = next_line;
         }
-        // exec_time.stop_print("file loaded");
-        // exec_time.start();
 
         //checks if parsing was valid(valid file & file has not changed)
-        debug_assert!((v_f64.capacity() == estimated_capacity) || (0 == estimated_capacity));
-        if file_state.load(Ordering::Relaxed) != FileState::Loading
-        {
-            //file selection has been changed OR wrong file format, ongoing file reading is outdated/invalid
-            return None;
-        }
-        if rounded_frames > frames
+        if cancel.is_cancelled()
         {
-            v_f64.resize(rounded_frames * rows * columns, 0.0);
-            // exec_time.stop_print("buffer resize");
-            // exec_time.start();
+            //file selection has been changed or app is closing, ongoing file reading is outdated
+            return Err(TTLoadError::Outdated);
         }
-        else
-        {
-            rounded_frames = frames;
-        }
-        match Array::from_shape_vec((rounded_frames, rows, columns), v_f64)
+        stack.entries = entries;
+        stack.timestamps = timestamps;
+        stack.into_input_data()
+    }
+
+    ///numbered png/tiff/radiometric jpeg frames (see `frame_files()`); only first page of each tiff file is used
+    fn data_load_image_directory(
+        dir : &Path,
+        cancel : &TTCancelToken,
+        progress : &TTProgress,
+    ) -> Result<TTInputData, TTLoadError>
+    {
+        let files = frame_files(dir)?;
+        let mut stack = FrameStack::default();
+        stack.expected_frames = files.len();
+        for (idx, file) in files.iter().enumerate()
         {
-            Ok(data) =>
+            progress.set(idx as f32 / files.len() as f32);
+            if cancel.is_cancelled()
+            {
+                //file selection has been changed or app is closing, ongoing file reading is outdated
+                return Err(TTLoadError::Outdated);
+            }
+            if has_extension(&file, EXTENSIONS_TIFF)
+            {
+                read_tiff(BufReader::new(File::open(file)?), &mut stack, false, cancel)?;
+                continue;
+            }
+            if has_extension(&file, EXTENSIONS_JPEG)
+            {
+                let fff = fff_from_jpeg(&fs::read(file)?)?;
+                let mut frames = 0;
+                read_fff_sequence(fff.as_slice(), |frame| {
+                    frames += 1;
+                    //radiometric jpeg contains single frame
+                    match frames
+                    {
+                        1 => stack.push_fff(frame),
+                        _ => Ok(()),
+                    }
+                })?;
+                continue;
+            }
+            let image = image::open(file)?;
+            let size = [image.width() as usize, image.height() as usize];
+            let values : Vec<f64> = match image
             {
-                // exec_time.stop_print("create array");
-                // exec_time.start();
-                if data.shape().iter().fold(true, |acc, &dim| acc & (dim > 1))
+                image::DynamicImage::ImageLuma8(image) =>
                 {
-                    //transpose/reverse axes to provide continous memory for across-time slices(faster ndfft)
-                    let mut data_transposed_uninit =
-                        Array::uninit((data.dim().2, data.dim().1, data.dim().0));
-                    Zip::from(&data.reversed_axes())
-                        .and(&mut data_transposed_uninit)
-                        .par_for_each(|i, o| {
-                            o.write(*i);
-                        });
-                    // exec_time.stop_print("reverse axes");
-                    // exec_time.start();
-                    let data_transposed = unsafe {
-                        // we can now promise we have fully initialized `data_transposed`.
-                        data_transposed_uninit.assume_init()
-                    };
-                    let ret = Some(TTInputData {
-                        data :   data_transposed,
-                        frames : frames,
-                        width :  columns,
-                        height : rows,
-                    });
-                    // exec_time.stop_print("end");
-                    return ret;
+                    image.into_raw().into_iter().map(f64::from).collect()
                 }
-                else
+                image::DynamicImage::ImageLuma16(image) =>
                 {
-                    //invalid data dimensions-> wrong file format/empty file
-                    ferror!(file_state);
+                    image.into_raw().into_iter().map(f64::from).collect()
                 }
-            }
-            Err(_) =>
-            {
-                //wrong file format
-                ferror!(file_state);
-            }
+                image =>
+                {
+                    return Err(TTLoadError::UnsupportedPixelFormat(format!(
+                        "{:?}",
+                        image.color()
+                    )))
+                }
+            };
+            stack.push(size, &values)?;
         }
+        stack.into_input_data()
     }
 
-    fn data_load_ttcf(mut f : File, file_state : Arc<AtomicFileState>) -> Option<TTInputData>
+    fn data_load_ttcf<R : Read>(mut f : R) -> Result<TTInputData, TTLoadError>
     {
         let mut header : [u8; 20] = [0u8; 20];
-        if let Err(_) = f.read_exact(&mut header)
-        {
-            ferror!(file_state);
-        }
+        f.read_exact(&mut header)?;
         let header_str = String::from_utf8_lossy(&header).to_string();
         let header_str = header_str.as_str().trim_end_matches("\0").trim();
         match header_str
         {
             HEADER_TTCF_V1 =>
             {
-                if let Ok(decoder) = zstd::Decoder::new(f)
+                let decoder = zstd::Decoder::new(f).map_err(TTLoadError::Zstd)?;
+                let mut reader = NoSeek::new(BufReader::new(decoder));
+                let mut data : TTInputData = FileTTCF::read_le(&mut reader)?.into();
+                //metadata block is optional (files written by older versions end after data)
+                if let Ok(metadata) = FileTTCFMetadata::read_le(&mut reader)
                 {
-                    if let Ok(data) = FileTTCF::read_le(&mut NoSeek::new(BufReader::new(decoder)))
-                    {
-                        return Some(data.into());
-                    }
-                    else
+                    data.metadata = metadata.into();
+                }
+                Ok(data)
+            }
+            HEADER_TTCF_V2 =>
+            {
+                let decoder = zstd::Decoder::new(f).map_err(TTLoadError::Zstd)?;
+                Self::read_ttcf_v2(BufReader::new(decoder))
+            }
+            _ => Err(TTLoadError::BadHeader(header_str.to_string())),
+        }
+    }
+    fn write_ttcf_v2<W : Write>(
+        &self,
+        writer : &mut W,
+        data : &TTInputData,
+        precision : TTCFPrecision,
+    ) -> io::Result<()>
+    {
+        let dimensions = ChunkDimensions {
+            frames :        data.frames as u32,
+            padded_frames : data.data.dim().2 as u32,
+            width :         data.width as u32,
+            height :        data.height as u32,
+        };
+        write_chunk(writer, CHUNK_DIMENSIONS, &dimensions)?;
+        let metadata = ChunkMetadata {
+            frame_rate : data.metadata.frame_rate.unwrap_or(0.0),
+            metadata :   (&data.metadata).into(),
+        };
+        write_chunk(writer, CHUNK_METADATA, &metadata)?;
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let source = ChunkSource {
+            name : (&name).into(),
+            hash : files_hash(&self.source_files()?)?,
+        };
+        write_chunk(writer, CHUNK_SOURCE, &source)?;
+
+        //data chunk is converted block by block (without buffering whole payload)
+        let values = data.data.as_standard_layout();
+        let values = values.as_slice().unwrap(); //standard layout is contiguous
+        let len = 1 + values.len() * precision.size();
+        write_chunk_header(writer, CHUNK_DATA, len)?;
+        writer.write_all(&[precision as u8])?;
+        let mut bytes = Vec::with_capacity(DATA_BLOCK_LEN * precision.size());
+        for block in values.chunks(DATA_BLOCK_LEN)
+        {
+            bytes.clear();
+            block.iter().for_each(|&x| precision.encode(x, &mut bytes));
+            writer.write_all(&bytes)?;
+        }
+        write_chunk_header(writer, CHUNK_END, 0)?;
+        writer.flush()
+    }
+    fn read_ttcf_v2<R : Read>(mut reader : R) -> Result<TTInputData, TTLoadError>
+    {
+        let mut dimensions : Option<ChunkDimensions> = None;
+        let mut metadata = TTMetadata::default();
+        let mut source : Option<ChunkSource> = None;
+        let mut values : Option<Vec<f64>> = None;
+        loop
+        {
+            let mut header = [0u8; CHUNK_HEADER_LEN];
+            reader
+                .read_exact(&mut header)
+                .map_err(truncated("missing end chunk"))?;
+            let header = ChunkHeader::read_le(&mut Cursor::new(header))?;
+            match header.id
+            {
+                CHUNK_END => break,
+                CHUNK_DIMENSIONS => dimensions = Some(read_chunk(&mut reader, header.len)?),
+                CHUNK_METADATA =>
+                {
+                    let chunk : ChunkMetadata = read_chunk(&mut reader, header.len)?;
+                    metadata = chunk.metadata.into();
+                    if chunk.frame_rate > 0.0
                     {
-                        ferror!(file_state);
+                        metadata.frame_rate = Some(chunk.frame_rate);
                     }
                 }
-                else
+                CHUNK_SOURCE => source = Some(read_chunk(&mut reader, header.len)?),
+                CHUNK_DATA =>
                 {
-                    ferror!(file_state);
+                    let dimensions = dimensions
+                        .as_ref()
+                        .ok_or(TTLoadError::Corrupted("data chunk before dimensions"))?;
+                    values = Some(read_values(
+                        &mut reader,
+                        header.len,
+                        data_count(dimensions)?,
+                    )?);
+                }
+                _ =>
+                {
+                    //chunk added in newer version; skip it
+                    io::copy(&mut (&mut reader).take(header.len), &mut io::sink())
+                        .map_err(TTLoadError::Zstd)?;
                 }
             }
-            _ =>
-            {
-                ferror!(file_state);
-            }
         }
+        let (dimensions, values) = match (dimensions, values)
+        {
+            (Some(dimensions), Some(values)) => (dimensions, values),
+            _ => return Err(TTLoadError::Corrupted("missing dimensions or data chunk")),
+        };
+        if let Some(source) = source
+        {
+            metadata.source_name = Some(source.name.into());
+            metadata.source_hash = Some(source.hash);
+        }
+        let shape = (
+            dimensions.width as usize,
+            dimensions.height as usize,
+            dimensions.padded_frames as usize,
+        );
+        let data = Array::from_shape_vec(shape, values)
+            .map_err(|_| TTLoadError::Corrupted("data does not match dimensions"))?;
+        Ok(TTInputData {
+            frames : dimensions.frames as usize,
+            width : shape.0,
+            height : shape.1,
+            data,
+            metadata,
+        })
     }
     //////////////
     //
     //////////////
     pub fn path(&self) -> String { self.path.to_string_lossy().to_string() }
+    ///valid after `data_load()`
+    pub fn is_ttcf(&self) -> bool { self.file_type == TTFileType::TTCF }
     //////////////
     // create TTFile
     //////////////
@@ -386,6 +1040,8 @@ impl TTFile
             .add_filter("*", &SUPPORTED_FILE_EXTENSIONS.concat())
             .add_filter("Text coded thermogram sequence", EXTENSIONS_TCTS)
             .add_filter("ThermoTransform compressed file", EXTENSIONS_TTCF)
+            .add_filter("Multi-page TIFF", EXTENSIONS_TIFF)
+            .add_filter("FLIR sequence", EXTENSIONS_SEQ)
             .pick_file()
             .map(|x| {
                 Self {
@@ -394,9 +1050,26 @@ impl TTFile
                 }
             })
     }
+    ///directory of numbered png/tiff frames
+    pub fn new_from_folder_dialog() -> Option<Self>
+    {
+        FileDialog::new().pick_folder().map(|x| {
+            Self {
+                path :      x,
+                file_type : TTFileType::Unknown,
+            }
+        })
+    }
 
     pub fn new_prevalidated(path : PathBuf) -> Option<Self>
     {
+        if path.is_dir()
+        {
+            return Some(Self {
+                path :      path,
+                file_type : TTFileType::Unknown,
+            });
+        }
         for ext in SUPPORTED_FILE_EXTENSIONS.concat()
         {
             if path.extension() == Some(OsString::from_str(ext).unwrap().as_os_str())
@@ -441,22 +1114,161 @@ impl Into<TTInputData> for FileTTCF
     fn into(self) -> TTInputData
     {
         TTInputData {
-            frames : self.frames as usize,
-            width :  self.width as usize,
-            height : self.height as usize,
-            data :   self.data,
+            frames :   self.frames as usize,
+            width :    self.width as usize,
+            height :   self.height as usize,
+            data :     self.data,
+            metadata : Default::default(),
         }
     }
 }
-impl From<&TTInputData> for FileTTCF
+
+///length prefixed UTF-8 string
+#[binrw]
+pub struct FileTTCFString
 {
-    fn from(value : &TTInputData) -> Self
-    {
-        Self {
-            frames : value.frames as u32,
-            width :  value.width as u32,
-            height : value.height as u32,
-            data :   value.data.clone(), //TODO !
+    #[br(temp)]
+    #[bw(calc = bytes.len() as u32)]
+    len :   u32,
+    #[br(count = len)]
+    bytes : Vec<u8>,
+}
+///appended after `FileTTCF` (inside same zstd stream) in v1, payload of `CHUNK_METADATA` in v2
+#[binrw]
+pub struct FileTTCFMetadata
+{
+    #[br(temp)]
+    #[bw(calc = entries.len() as u32)]
+    entries_count :    u32,
+    #[br(count = entries_count)]
+    pub entries :      Vec<(FileTTCFString, FileTTCFString)>,
+    #[br(temp)]
+    #[bw(calc = timestamps.len() as u32)]
+    timestamps_count : u32,
+    #[br(count = timestamps_count)]
+    pub timestamps :   Vec<f64>,
+}
+impl From<&String> for FileTTCFString
+{
+    fn from(value : &String) -> Self
+    {
+        Self {
+            bytes : value.as_bytes().to_vec(),
+        }
+    }
+}
+impl Into<String> for FileTTCFString
+{
+    fn into(self) -> String { String::from_utf8_lossy(&self.bytes).to_string() }
+}
+impl Into<TTMetadata> for FileTTCFMetadata
+{
+    fn into(self) -> TTMetadata
+    {
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        TTMetadata::new(entries, self.timestamps)
+    }
+}
+impl From<&TTMetadata> for FileTTCFMetadata
+{
+    fn from(value : &TTMetadata) -> Self
+    {
+        Self {
+            entries :    value
+                .entries
+                .iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            timestamps : value.timestamps.clone(),
+        }
+    }
+}
+
+#[binrw]
+pub struct ChunkHeader
+{
+    pub id :  [u8; 4],
+    ///payload length [bytes]
+    pub len : u64,
+}
+#[binrw]
+pub struct ChunkDimensions
+{
+    pub frames :        u32,
+    ///frames including zero padding (stored data length along time axis)
+    pub padded_frames : u32,
+    pub width :         u32,
+    pub height :        u32,
+}
+#[binrw]
+pub struct ChunkMetadata
+{
+    ///0 if unknown
+    pub frame_rate : f64,
+    pub metadata :   FileTTCFMetadata,
+}
+///file converted to ttcf
+#[binrw]
+pub struct ChunkSource
+{
+    pub name : FileTTCFString,
+    ///see `files_hash()`
+    pub hash : u64,
+}
+impl TTCFPrecision
+{
+    ///bytes per stored value
+    fn size(self) -> usize
+    {
+        match self
+        {
+            TTCFPrecision::Lossy_22_Bit | TTCFPrecision::F32 => 4,
+            TTCFPrecision::F64 => 8,
+        }
+    }
+    fn from_id(id : u8) -> Option<Self>
+    {
+        [
+            TTCFPrecision::Lossy_22_Bit,
+            TTCFPrecision::F32,
+            TTCFPrecision::F64,
+        ]
+        .into_iter()
+        .find(|&precision| precision as u8 == id)
+    }
+    fn encode(self, x : f64, bytes : &mut Vec<u8>)
+    {
+        match self
+        {
+            TTCFPrecision::Lossy_22_Bit => bytes.extend(f64_compress(x).to_le_bytes()),
+            TTCFPrecision::F32 => bytes.extend((x as f32).to_le_bytes()),
+            TTCFPrecision::F64 => bytes.extend(x.to_le_bytes()),
+        }
+    }
+    ///value after storing & reading back
+    fn round_trip(self, x : f64) -> f64
+    {
+        match self
+        {
+            TTCFPrecision::Lossy_22_Bit => f64_decompress(f64_compress(x)),
+            TTCFPrecision::F32 => x as f32 as f64,
+            TTCFPrecision::F64 => x,
+        }
+    }
+    fn decode(self, bytes : &[u8]) -> f64
+    {
+        match self
+        {
+            TTCFPrecision::Lossy_22_Bit =>
+            {
+                f64_decompress(u32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            TTCFPrecision::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            TTCFPrecision::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
         }
     }
 }
@@ -624,6 +1436,449 @@ fn f64_decompress(x : u32) -> f64
     f32::from_bits(f32_bits) as f64
 }
 
+fn invalid_data<E : ToString>(error : E) -> io::Error
+{
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+///rename with copy & remove fallback (rename fails across file systems)
+fn move_file(from : &Path, to : &Path) -> io::Result<()>
+{
+    if fs::rename(from, to).is_err()
+    {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+fn write_chunk_header<W : Write>(writer : &mut W, id : [u8; 4], len : usize) -> io::Result<()>
+{
+    ChunkHeader {
+        id,
+        len : len as u64,
+    }
+    .write_le(&mut NoSeek::new(writer))
+    .map_err(invalid_data)
+}
+fn write_chunk<W, T>(writer : &mut W, id : [u8; 4], chunk : &T) -> io::Result<()>
+where
+    W : Write,
+    T : for<'a> BinWrite<Args<'a> = ()>,
+{
+    let mut payload = Cursor::new(Vec::new());
+    chunk.write_le(&mut payload).map_err(invalid_data)?;
+    let payload = payload.into_inner();
+    write_chunk_header(writer, id, payload.len())?;
+    writer.write_all(&payload)
+}
+fn read_chunk<R, T>(reader : &mut R, len : u64) -> Result<T, TTLoadError>
+where
+    R : Read,
+    T : for<'a> BinRead<Args<'a> = ()>,
+{
+    let mut payload = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut payload)
+        .map_err(TTLoadError::Zstd)?;
+    T::read_le(&mut Cursor::new(payload)).map_err(TTLoadError::Parse)
+}
+///unexpected end of (decompressed) stream means that file is corrupted
+fn truncated(reason : &'static str) -> impl Fn(io::Error) -> TTLoadError
+{
+    move |error| {
+        if error.kind() == io::ErrorKind::UnexpectedEof
+        {
+            TTLoadError::Corrupted(reason)
+        }
+        else
+        {
+            TTLoadError::Zstd(error)
+        }
+    }
+}
+///count of values in `CHUNK_DATA` for (checked) `dimensions`
+fn data_count(dimensions : &ChunkDimensions) -> Result<usize, TTLoadError>
+{
+    if dimensions.width < 2 || dimensions.height < 2
+    {
+        return Err(TTLoadError::Corrupted("image smaller than 2x2"));
+    }
+    if dimensions.frames < 2 || dimensions.frames > dimensions.padded_frames
+    {
+        return Err(TTLoadError::Corrupted("invalid frames count"));
+    }
+    (dimensions.width as usize)
+        .checked_mul(dimensions.height as usize)
+        .and_then(|x| x.checked_mul(dimensions.padded_frames as usize))
+        .ok_or(TTLoadError::Corrupted("dimensions too large"))
+}
+///payload of `CHUNK_DATA`: precision id followed by `count` values
+fn read_values<R : Read>(reader : &mut R, len : u64, count : usize)
+    -> Result<Vec<f64>, TTLoadError>
+{
+    let mut id = [0u8];
+    reader
+        .read_exact(&mut id)
+        .map_err(truncated("truncated data chunk"))?;
+    let precision =
+        TTCFPrecision::from_id(id[0]).ok_or(TTLoadError::Corrupted("unknown precision"))?;
+    let expected_len = count
+        .checked_mul(precision.size())
+        .and_then(|x| x.checked_add(1))
+        .ok_or(TTLoadError::Corrupted("data chunk too large"))?;
+    if len != expected_len as u64
+    {
+        return Err(TTLoadError::Corrupted(
+            "data chunk length does not match dimensions",
+        ));
+    }
+    //claimed size is not trusted until values are actually read
+    let mut values = Vec::with_capacity(count.min(DATA_BLOCK_LEN));
+    let mut bytes = vec![0u8; DATA_BLOCK_LEN * precision.size()];
+    while values.len() < count
+    {
+        let block = (count - values.len()).min(DATA_BLOCK_LEN) * precision.size();
+        reader
+            .read_exact(&mut bytes[..block])
+            .map_err(truncated("truncated data chunk"))?;
+        values.extend(
+            bytes[..block]
+                .chunks_exact(precision.size())
+                .map(|x| precision.decode(x)),
+        );
+    }
+    Ok(values)
+}
+///checks rows count of just parsed `frame` (1-based; 0 if no frame has been parsed yet)
+fn check_rows(
+    expected_rows : &mut Option<usize>,
+    frame : usize,
+    rows : usize,
+) -> Result<(), TTLoadError>
+{
+    if frame == 0
+    {
+        return Ok(());
+    }
+    match *expected_rows
+    {
+        None => *expected_rows = Some(rows),
+        Some(expected) if expected != rows =>
+        {
+            return Err(TTLoadError::InconsistentRows {
+                frame,
+                expected,
+                found : rows,
+            });
+        }
+        Some(_) => (),
+    }
+    Ok(())
+}
+///64-bit FNV-1a hash of content of consecutive files
+fn files_hash(paths : &[PathBuf]) -> io::Result<u64>
+{
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for path in paths
+    {
+        let mut reader = BufReader::new(File::open(path)?);
+        loop
+        {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty()
+            {
+                break;
+            }
+            hash = buf.iter().fold(hash, |hash, &x| {
+                (hash ^ x as u64).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+            let len = buf.len();
+            reader.consume(len);
+        }
+    }
+    Ok(hash)
+}
+///case insensitive
+fn has_extension(path : &Path, extensions : &[&str]) -> bool
+{
+    path.extension().map_or(false, |ext| {
+        extensions.contains(&ext.to_string_lossy().to_lowercase().as_str())
+    })
+}
+///png/tiff files in `dir` ordered by frame number (last number in file name, eg. "frame_0012.png"),
+///files without number go first
+fn frame_files(dir : &Path) -> io::Result<Vec<PathBuf>>
+{
+    let mut files : Vec<(Option<u64>, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && has_extension(path, &FRAME_FILE_EXTENSIONS.concat()))
+        .map(|path| (frame_number(&path), path))
+        .collect();
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+fn frame_number(path : &Path) -> Option<u64>
+{
+    let stem = path.file_stem()?.to_string_lossy();
+    let end = stem.rfind(|c : char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c : char| !c.is_ascii_digit())
+        .map_or(0, |idx| idx + 1);
+    stem[start..end].parse().ok()
+}
+///appends pages of tiff to `stack` (all of them or only first one)
+fn read_tiff<R : Read + Seek>(
+    reader : R,
+    stack : &mut FrameStack,
+    all_pages : bool,
+    cancel : &TTCancelToken,
+) -> Result<(), TTLoadError>
+{
+    use tiff::decoder::{Decoder, DecodingResult};
+    let mut decoder = Decoder::new(reader)?;
+    loop
+    {
+        if cancel.is_cancelled()
+        {
+            //file selection has been changed or app is closing, ongoing file reading is outdated
+            return Err(TTLoadError::Outdated);
+        }
+        let (width, height) = decoder.dimensions()?;
+        match decoder.colortype()?
+        {
+            tiff::ColorType::Gray(_) => (),
+            color => return Err(TTLoadError::UnsupportedPixelFormat(format!("{:?}", color))),
+        }
+        let values = match decoder.read_image()?
+        {
+            DecodingResult::U8(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U16(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U64(v) => v.into_iter().map(|x| x as f64).collect(),
+            DecodingResult::I8(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I16(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I64(v) => v.into_iter().map(|x| x as f64).collect(),
+            DecodingResult::F32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::F64(v) => v,
+        };
+        stack.push([width as usize, height as usize], &values)?;
+        if !all_pages || !decoder.more_images()
+        {
+            return Ok(());
+        }
+        decoder.next_image()?;
+    }
+}
+///reads until `buf` is full or end of file is reached; returns number of bytes read
+fn read_block<R : Read>(reader : &mut R, buf : &mut [u8]) -> io::Result<usize>
+{
+    let mut read = 0;
+    while read < buf.len()
+    {
+        match reader.read(&mut buf[read..])
+        {
+            Ok(0) => break,
+            Ok(len) => read += len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+fn text_line<'a>(line : &'a [u8], parser : &FastFloatParser<f64>) -> TextLine<'a>
+{
+    let line = match std::str::from_utf8(line)
+    {
+        Ok(line) => line.strip_suffix('\r').unwrap_or(line),
+        Err(_) => return TextLine::Empty,
+    };
+    if line.is_empty()
+    {
+        return TextLine::Empty;
+    }
+    let line = line.trim_start();
+    if parser.parse_partial(line).is_ok()
+    {
+        TextLine::Numeric(line)
+    }
+    else
+    {
+        TextLine::Header(line)
+    }
+}
+///splits `text` (starting at line `first_line` of file) into frames; frame starts with first header line
+///following numeric line; unless `eof`, text after last frame start is left (it may be incomplete);
+///returns frames & byte offset & line number of not split text
+fn split_text_frames(
+    text : &[u8],
+    first_line : usize,
+    separator : u8,
+    eof : bool,
+) -> (Vec<TextFrame>, usize, usize)
+{
+    let parser = FastFloatParser::<f64>::new(separator);
+    let mut frames = Vec::new();
+    let (mut start, mut start_line) = (0, first_line);
+    let (mut pos, mut line) = (0, first_line);
+    let mut previous_numeric = false;
+    while pos < text.len()
+    {
+        let end = match text[pos..].iter().position(|&c| c == b'\n')
+        {
+            Some(idx) => pos + idx + 1,
+            None if eof => text.len(),
+            //incomplete line
+            None => break,
+        };
+        let line_text = &text[pos..end];
+        match text_line(line_text.strip_suffix(b"\n").unwrap_or(line_text), &parser)
+        {
+            TextLine::Empty => (),
+            TextLine::Numeric(_) => previous_numeric = true,
+            TextLine::Header(_) =>
+            {
+                if previous_numeric && pos > start
+                {
+                    frames.push(TextFrame {
+                        range :      start..pos,
+                        first_line : start_line,
+                    });
+                    (start, start_line) = (pos, line);
+                }
+                previous_numeric = false;
+            }
+        }
+        pos = end;
+        line += 1;
+    }
+    if eof && start < text.len()
+    {
+        frames.push(TextFrame {
+            range :      start..text.len(),
+            first_line : start_line,
+        });
+        (start, start_line) = (text.len(), line);
+    }
+    (frames, start, start_line)
+}
+///parses frame found by `split_text_frames()`
+fn parse_text_frame(text : &[u8], first_line : usize, separator : u8) -> ParsedTextFrame
+{
+    let parser = FastFloatParser::<f64>::new(separator);
+    let mut parsed = ParsedTextFrame::default();
+    let mut header = false;
+    for (idx, line) in text.split(|&c| c == b'\n').enumerate()
+    {
+        match text_line(line, &parser)
+        {
+            TextLine::Empty => (),
+            TextLine::Header(line) =>
+            {
+                header = true;
+                if let Some((key, value)) = header_entry(line)
+                {
+                    if is_timestamp_key(&key) && parsed.timestamp.is_none()
+                    {
+                        parsed.timestamp = parse_time(&value);
+                    }
+                    if !parsed.entries.iter().any(|(k, _)| *k == key)
+                    {
+                        parsed.entries.push((key, value));
+                    }
+                }
+            }
+            //first line with numbers after header contains column id's; skip it
+            //(numbers before any header do not belong to frame)
+            TextLine::Numeric(_) if !parsed.is_frame => parsed.is_frame = header,
+            TextLine::Numeric(mut v) =>
+            {
+                //skip row id
+                if let Ok((_row_id, parsed_bytes)) = parser.parse_partial(v)
+                {
+                    v = &v[parsed_bytes..];
+                }
+                let mut columns = 0;
+                loop
+                {
+                    v = v.trim_start();
+                    match parser.parse_partial(v)
+                    {
+                        Ok((val, parsed_bytes)) =>
+                        {
+                            columns += 1;
+                            parsed.values.push(val);
+                            v = &v[parsed_bytes..];
+                        }
+                        Err(_) => break,
+                    }
+                }
+                if parsed.rows == 0
+                {
+                    parsed.first_data_line = first_line + idx;
+                }
+                parsed.rows += 1;
+                match parsed.columns
+                {
+                    None => parsed.columns = Some(columns),
+                    Some(expected) if expected != columns && parsed.column_error.is_none() =>
+                    {
+                        parsed.column_error = Some((first_line + idx, expected, columns));
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+    }
+    parsed
+}
+///splits header line into (key, value) at first ':', '=' or tab;
+///line without separator is split before first digit (eg. "Emissivity 0.95")
+fn header_entry(line : &str) -> Option<(String, String)>
+{
+    let (key, value) = match line.find(|c : char| matches!(c, ':' | '=' | '\t'))
+    {
+        Some(idx) => (&line[..idx], &line[idx + 1..]),
+        None => line.split_at(line.find(|c : char| c.is_ascii_digit())?),
+    };
+    let (key, value) = (key.trim(), value.trim());
+    (!key.is_empty() && !value.is_empty()).then(|| (key.to_string(), value.to_string()))
+}
+fn is_timestamp_key(key : &str) -> bool
+{
+    let key = key.to_lowercase();
+    matches!(
+        key.as_str(),
+        "time" | "frame time" | "relative time" | "rel. time"
+    ) || key.contains("timestamp")
+}
+///frame time [s] in "[date] hh:mm:ss.sss" format or as plain number of seconds (or milliseconds with "ms" suffix)
+fn parse_time(value : &str) -> Option<f64>
+{
+    match value.split_whitespace().rev().find(|x| x.contains(':'))
+    {
+        Some(time) =>
+        {
+            time.split(':')
+                .try_fold(0.0, |acc, part| Some(acc * 60.0 + parse_leading_f64(part)?))
+        }
+        None =>
+        {
+            let time = parse_leading_f64(value)?;
+            Some(
+                if value.ends_with("ms")
+                {
+                    1e-3 * time
+                }
+                else
+                {
+                    time
+                },
+            )
+        }
+    }
+}
+
 const PRIMES : &'static [usize] = array_pows_2_3!();
 fn find_next_pows_2_3(val : usize) -> usize
 {
@@ -638,3 +1893,472 @@ fn find_next_pows_2_3(val : usize) -> usize
         }
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use ndarray::Array2;
+
+    ///empty temporary directory for test `name`
+    fn temp_dir(name : &str) -> PathBuf
+    {
+        let dir = std::env::temp_dir().join(format!("tt_file_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    fn synthetic_input(width : usize, height : usize, frames : usize) -> TTInputData
+    {
+        let padded = find_next_pows_2_3(frames);
+        TTInputData {
+            frames,
+            width,
+            height,
+            data : Array3::from_shape_fn((width, height, padded), |(x, y, t)| {
+                if t < frames
+                {
+                    20.0 + 0.37 * x as f64 - 0.11 * y as f64 + (0.5 * t as f64).sin()
+                }
+                else
+                {
+                    0.0
+                }
+            }),
+            metadata : TTMetadata::new(
+                vec![
+                    ("Camera".to_string(), "Test".to_string()),
+                    ("Emissivity".to_string(), "0.95".to_string()),
+                ],
+                (0..frames).map(|t| 0.02 * t as f64).collect(),
+            ),
+        }
+    }
+    fn load(path : &Path) -> Result<TTInputData, TTLoadError>
+    {
+        TTFile::from(path.as_os_str().to_owned()).data_load(
+            Arc::new(AtomicFileState::new(FileState::Loading)),
+            &Default::default(),
+            &Default::default(),
+        )
+    }
+    ///uncompressed v2 chunk stream of `data` converted from `source`
+    fn ttcf_v2_stream(source : &Path, data : &TTInputData) -> Vec<u8>
+    {
+        let mut stream = Vec::new();
+        TTFile::from(source.as_os_str().to_owned())
+            .write_ttcf_v2(&mut stream, data, TTCFPrecision::F64)
+            .unwrap();
+        stream
+    }
+
+    #[test]
+    fn ttcf_round_trip_with_each_precision()
+    {
+        let dir = temp_dir("ttcf_round_trip");
+        let source = dir.join("scan.txt");
+        fs::write(&source, "source content").unwrap();
+        let file = TTFile::from(source.clone().into_os_string());
+        let data = synthetic_input(5, 4, 10);
+        let path = dir.join("scan.ttcf");
+        //relative tolerance: 13 bits of significand are kept by lossy precision
+        for (precision, tolerance) in [
+            (TTCFPrecision::Lossy_22_Bit, 2e-4),
+            (TTCFPrecision::F32, 1e-7),
+            (TTCFPrecision::F64, 0.0),
+        ]
+        {
+            file.write_ttcf_file(&path, &data, precision).unwrap();
+            let loaded = load(&path).unwrap();
+            assert_eq!((loaded.frames, loaded.width, loaded.height), (10, 5, 4));
+            assert_eq!(loaded.data.dim(), data.data.dim());
+            assert!(loaded
+                .data
+                .iter()
+                .zip(data.data.iter())
+                .all(|(a, b)| (a - b).abs() <= tolerance * b.abs()));
+            let metadata = TTMetadata {
+                source_name : Some("scan.txt".to_string()),
+                source_hash : Some(files_hash(&[source.clone()]).unwrap()),
+                ..data.metadata.clone()
+            };
+            assert!(loaded.metadata == metadata);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ttcf_unknown_chunk_is_skipped()
+    {
+        let dir = temp_dir("ttcf_unknown_chunk");
+        let source = dir.join("scan.txt");
+        fs::write(&source, "source content").unwrap();
+        let data = synthetic_input(3, 2, 6);
+        let mut stream = Vec::new();
+        write_chunk_header(&mut stream, *b"XTRA", 3).unwrap();
+        stream.extend([1, 2, 3]);
+        stream.extend(ttcf_v2_stream(&source, &data));
+        let loaded = TTFile::read_ttcf_v2(stream.as_slice()).unwrap();
+        assert!(loaded.data == data.data);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ttcf_truncated_stream_is_corrupted()
+    {
+        let dir = temp_dir("ttcf_truncated");
+        let source = dir.join("scan.txt");
+        fs::write(&source, "source content").unwrap();
+        let stream = ttcf_v2_stream(&source, &synthetic_input(3, 2, 6));
+        let without_end = &stream[..stream.len() - CHUNK_HEADER_LEN];
+        let truncated_data = &without_end[..without_end.len() - 5];
+        for stream in [without_end, truncated_data]
+        {
+            assert!(matches!(
+                TTFile::read_ttcf_v2(stream),
+                Err(TTLoadError::Corrupted(_))
+            ));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ttcf_v1_is_still_read()
+    {
+        let data = synthetic_input(3, 2, 6);
+        let mut payload = Cursor::new(Vec::new());
+        FileTTCF {
+            frames : 6,
+            width :  3,
+            height : 2,
+            data :   data.data.clone(),
+        }
+        .write_le(&mut payload)
+        .unwrap();
+        FileTTCFMetadata::from(&data.metadata)
+            .write_le(&mut payload)
+            .unwrap();
+        let mut file = HEADER_TTCF_V1.as_bytes().to_vec();
+        file.resize(20, 0);
+        file.extend(zstd::encode_all(payload.get_ref().as_slice(), 3).unwrap());
+
+        let loaded = TTFile::data_load_ttcf(file.as_slice()).unwrap();
+        assert_eq!((loaded.frames, loaded.width, loaded.height), (6, 3, 2));
+        assert!(
+            loaded.data
+                == data
+                    .data
+                    .mapv(|x| TTCFPrecision::Lossy_22_Bit.round_trip(x))
+        );
+        assert!(loaded.metadata == data.metadata);
+    }
+
+    ///file header, frames with sub-header (frame time) & trailing line; pixel value is x + 10y + 100t + 0.5
+    fn text_file(width : usize, height : usize, frames : usize, decimal : &str) -> String
+    {
+        let mut text = String::from("Camera: Test\nFrame rate: 50 Hz\n");
+        for t in 0..frames
+        {
+            text += &format!("Frame: {}\nTime: 00:00:{:02}.500\n", t + 1, t);
+            let ids : Vec<String> = (0..width).map(|x| x.to_string()).collect();
+            text += &format!("{}\n", ids.join(" "));
+            for y in 0..height
+            {
+                let row : Vec<String> = (0..width)
+                    .map(|x| format!("{}{}5", x + 10 * y + 100 * t, decimal))
+                    .collect();
+                text += &format!("{} {}\n", y, row.join("\t"));
+            }
+        }
+        text + "Recording finished\n"
+    }
+    fn load_text(text : &str, block_len : usize) -> Result<TTInputData, TTLoadError>
+    {
+        TTFile::data_load_tcts(text.as_bytes(), text.len(), &Default::default(), block_len)
+    }
+    fn check_text_input(input : &TTInputData, width : usize, height : usize, frames : usize)
+    {
+        assert_eq!(
+            (input.width, input.height, input.frames),
+            (width, height, frames)
+        );
+        for ((x, y, t), &value) in input.data.indexed_iter()
+        {
+            let expected = if t < frames
+            {
+                (x + 10 * y + 100 * t) as f64 + 0.5
+            }
+            else
+            {
+                0.0
+            };
+            assert_eq!(value, expected, "pixel ({}, {}), frame {}", x, y, t);
+        }
+        assert_eq!(input.metadata.camera.as_deref(), Some("Test"));
+        assert_eq!(input.metadata.frame_rate, Some(50.0));
+        let timestamps : Vec<f64> = (0..frames).map(|t| t as f64).collect();
+        assert_eq!(input.metadata.timestamps, timestamps);
+    }
+
+    #[test]
+    fn text_frames_spanning_blocks()
+    {
+        let text = text_file(4, 3, 5, ".");
+        //blocks shorter than line, shorter than frame & whole file
+        for block_len in [7, 64, 1 << 20]
+        {
+            check_text_input(&load_text(&text, block_len).unwrap(), 4, 3, 5);
+        }
+    }
+
+    #[test]
+    fn text_with_decimal_comma()
+    {
+        let text = text_file(4, 3, 5, ",");
+        //decimal separator is detected in first block, so it has to contain some values
+        check_text_input(&load_text(&text, 128).unwrap(), 4, 3, 5);
+    }
+
+    #[test]
+    fn text_inconsistent_frames_report_line_and_frame()
+    {
+        let frame =
+            |id : usize, rows : &[&str]| format!("Frame: {}\n0 1 2\n{}\n", id, rows.join("\n"));
+        let full = ["0 1.0 2.0 3.0", "1 4.0 5.0 6.0"];
+        //lines: 1 header, 2-5 frame 1, 6 frame 2 header, 7 column ids, 8-9 data
+        let ragged = String::from("Camera: Test\n")
+            + &frame(1, &full)
+            + &frame(2, &["0 1.0 2.0 3.0", "1 4.0 5.0"])
+            + &frame(3, &full);
+        let narrow = String::from("Camera: Test\n")
+            + &frame(1, &full)
+            + &frame(2, &["0 1.0 2.0", "1 4.0 5.0"])
+            + &frame(3, &full);
+        let short = String::from("Camera: Test\n")
+            + &frame(1, &full)
+            + &frame(2, &["0 1.0 2.0 3.0"])
+            + &frame(3, &full);
+        for block_len in [7, 1 << 20]
+        {
+            let error = load_text(&ragged, block_len).err().unwrap();
+            assert!(
+                matches!(
+                    error,
+                    TTLoadError::InconsistentColumns {
+                        line :     9,
+                        frame :    2,
+                        expected : 3,
+                        found :    2,
+                    }
+                ),
+                "{}",
+                error
+            );
+            let error = load_text(&narrow, block_len).err().unwrap();
+            assert!(
+                matches!(
+                    error,
+                    TTLoadError::InconsistentColumns {
+                        line :     8,
+                        frame :    2,
+                        expected : 3,
+                        found :    2,
+                    }
+                ),
+                "{}",
+                error
+            );
+            let error = load_text(&short, block_len).err().unwrap();
+            assert!(
+                matches!(
+                    error,
+                    TTLoadError::InconsistentRows {
+                        frame :    2,
+                        expected : 2,
+                        found :    1,
+                    }
+                ),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn frame_stack_grows_beyond_expected_frames()
+    {
+        let (width, height, frames) = (5, 3, 20);
+        let mut stack = FrameStack {
+            expected_frames : 3,
+            ..Default::default()
+        };
+        for t in 0..frames
+        {
+            let frame : Vec<f64> = (0..width * height)
+                .map(|i| (i % width + 10 * (i / width) + 100 * t) as f64)
+                .collect();
+            stack.push([width, height], &frame).unwrap();
+            //flush every frame, so time axis has to grow several times
+            stack.flush();
+        }
+        let input = stack.into_input_data().unwrap();
+        assert_eq!(
+            (input.width, input.height, input.frames),
+            (width, height, frames)
+        );
+        assert_eq!(
+            input.data.dim(),
+            (width, height, find_next_pows_2_3(frames))
+        );
+        for ((x, y, t), &value) in input.data.indexed_iter()
+        {
+            let expected = if t < frames
+            {
+                (x + 10 * y + 100 * t) as f64
+            }
+            else
+            {
+                0.0
+            };
+            assert_eq!(value, expected, "pixel ({}, {}), frame {}", x, y, t);
+        }
+    }
+
+    #[test]
+    fn text_non_square_geometry()
+    {
+        //wide frame like 640x512 camera, scaled down
+        let (width, height, frames) = (10, 8, 6);
+        let input = load_text(&text_file(width, height, frames, "."), 256).unwrap();
+        assert_eq!((input.width, input.height), (width, height));
+        let series : Vec<f64> = (0..frames).map(|t| input.data[[7, 2, t]]).collect();
+        let expected : Vec<f64> = (0..frames).map(|t| (27 + 100 * t) as f64 + 0.5).collect();
+        assert_eq!(series, expected);
+    }
+
+    ///frame t of image stack: pixel value is x + 10y + 100t
+    fn stack_frame(width : usize, height : usize, t : usize) -> Array2<f64>
+    {
+        Array2::from_shape_fn((width, height), |(x, y)| (x + 10 * y + 100 * t) as f64)
+    }
+    fn check_stack_input(input : &TTInputData, frames : &[Array2<f64>])
+    {
+        let (width, height) = frames[0].dim();
+        assert_eq!(
+            (input.width, input.height, input.frames),
+            (width, height, frames.len())
+        );
+        for (t, frame) in frames.iter().enumerate()
+        {
+            assert!(
+                input.data.index_axis(ndarray::Axis(2), t) == frame,
+                "frame {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn frame_number_is_last_number_of_stem()
+    {
+        assert_eq!(frame_number(Path::new("frame_0012.png")), Some(12));
+        assert_eq!(frame_number(Path::new("cam2_frame7.tif")), Some(7));
+        assert_eq!(frame_number(Path::new("0001.tiff")), Some(1));
+        assert_eq!(frame_number(Path::new("12/cover.png")), None);
+    }
+
+    #[test]
+    fn frame_files_are_ordered_by_number()
+    {
+        let dir = temp_dir("frame_files");
+        for name in [
+            "frame_10.png",
+            "frame_2.PNG",
+            "frame_1.tif",
+            "frame_0003.png",
+            "cover.png",
+            "notes.txt",
+        ]
+        {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let files : Vec<String> = frame_files(&dir)
+            .unwrap()
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                "cover.png",
+                "frame_1.tif",
+                "frame_2.PNG",
+                "frame_0003.png",
+                "frame_10.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn read_multi_page_tiff()
+    {
+        use tiff::encoder::{colortype::Gray16, TiffEncoder};
+        let (width, height) = (4, 3);
+        let frames : Vec<Array2<f64>> = (0..3).map(|t| stack_frame(width, height, t)).collect();
+        let mut file = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut file).unwrap();
+        for frame in &frames
+        {
+            //row-major page
+            let page : Vec<u16> = frame.t().iter().map(|&x| x as u16).collect();
+            encoder
+                .write_image::<Gray16>(width as u32, height as u32, &page)
+                .unwrap();
+        }
+        file.set_position(0);
+        let mut stack = FrameStack::default();
+        read_tiff(&mut file, &mut stack, true, &Default::default()).unwrap();
+        check_stack_input(&stack.into_input_data().unwrap(), &frames);
+        //only first page
+        file.set_position(0);
+        let mut stack = FrameStack::default();
+        read_tiff(&mut file, &mut stack, false, &Default::default()).unwrap();
+        assert_eq!(stack.frames, 1);
+    }
+
+    #[test]
+    fn exported_image_stacks_load_back()
+    {
+        let (width, height) = (4, 3);
+        let mut frames : Vec<Array2<f64>> = (0..3).map(|t| stack_frame(width, height, t)).collect();
+        //png values are mapped from [min; max] of stack to [0; 65535], so these extremes keep values unchanged
+        frames[2][[width - 1, height - 1]] = u16::MAX as f64;
+        let views : Vec<TTRawView> = frames
+            .iter()
+            .map(|frame| {
+                TTRawView {
+                    data :   frame.clone(),
+                    axes :   [TTAxis::X, TTAxis::Y],
+                    offset : [0, 0],
+                }
+            })
+            .collect();
+        let dir = temp_dir("image_stacks");
+        let tiff = dir.join("stack.TIF");
+        TTRawView::export_stack(&views, &tiff).unwrap();
+        check_stack_input(&load(&tiff).unwrap(), &frames);
+        let png_dir = dir.join("png");
+        fs::create_dir(&png_dir).unwrap();
+        TTRawView::export_stack(&views, &png_dir.join("stack.png")).unwrap();
+        check_stack_input(&load(&png_dir).unwrap(), &frames);
+    }
+
+    #[test]
+    fn extensions_are_case_insensitive()
+    {
+        let dir = temp_dir("extension_case");
+        let path = dir.join("sequence.TXT");
+        fs::write(&path, text_file(4, 3, 5, ".")).unwrap();
+        check_text_input(&load(&path).unwrap(), 4, 3, 5);
+    }
+}
@@ -0,0 +1,758 @@
+//! FLIR FFF records: `.seq` sequence is concatenation of records, radiometric jpeg holds single record
+//! split into APP1 "FLIR" segments; record contains raw sensor counts & radiometric calibration of camera
+use image::ImageFormat;
+use std::io::{self, Read};
+use std::ops::Range;
+
+use crate::tt_file::TTLoadError;
+
+//=======================================
+//================Consts=================
+//=======================================
+
+const FFF_MAGIC : [u8; 4] = *b"FFF\0";
+const FFF_HEADER_LEN : usize = 64;
+const FFF_INDEX_ENTRY_LEN : usize = 32;
+///limits protecting against huge allocations on corrupted files
+const FFF_MAX_INDEX_ENTRIES : usize = 1024;
+const FFF_MAX_RECORD_LEN : usize = 1 << 30;
+///index entry types
+const FFF_RAW_DATA : u16 = 0x01;
+const FFF_CAMERA_INFO : u16 = 0x20;
+///offset of pixels in raw data block
+const RAW_DATA_PIXELS : usize = 0x20;
+///camera info block has to contain at least Planck constants
+const CAMERA_INFO_MIN_LEN : usize = 0x310;
+const PNG_SIGNATURE : &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_FLIR_SEGMENT : &[u8] = b"FLIR\0";
+const KELVIN : f64 = 273.15;
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///byte order of FFF record/block
+#[derive(Clone, Copy)]
+enum Endian
+{
+    Big,
+    Little,
+}
+
+///radiometric params from camera info block (temperatures in K, distance in m)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FFFCalibration
+{
+    pub emissivity :          f64,
+    pub object_distance :     f64,
+    pub reflected_temp :      f64,
+    pub atmosphere_temp :     f64,
+    pub window_temp :         f64,
+    pub window_transmission : f64,
+    ///0-1
+    pub relative_humidity :   f64,
+    pub planck_r1 :           f64,
+    pub planck_r2 :           f64,
+    pub planck_b :            f64,
+    pub planck_f :            f64,
+    pub planck_o :            f64,
+    ///atmospheric transmission coefficients
+    pub atm_alpha :           [f64; 2],
+    pub atm_beta :            [f64; 2],
+    pub atm_x :               f64,
+}
+
+///single frame converted to temperature [°C]
+pub struct FFFFrame
+{
+    pub width :        usize,
+    pub height :       usize,
+    ///row-major
+    pub temperatures : Vec<f64>,
+    pub camera :       String,
+    pub calibration :  FFFCalibration,
+    ///acquisition time [s] (unix time)
+    pub time :         Option<f64>,
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl Endian
+{
+    fn bytes<const N: usize>(data : &[u8], offset : usize) -> Option<[u8; N]>
+    {
+        data.get(offset..offset + N)?.try_into().ok()
+    }
+    fn u16(self, data : &[u8], offset : usize) -> Option<u16>
+    {
+        let bytes = Self::bytes(data, offset)?;
+        Some(match self
+        {
+            Endian::Big => u16::from_be_bytes(bytes),
+            Endian::Little => u16::from_le_bytes(bytes),
+        })
+    }
+    fn u32(self, data : &[u8], offset : usize) -> Option<u32>
+    {
+        let bytes = Self::bytes(data, offset)?;
+        Some(match self
+        {
+            Endian::Big => u32::from_be_bytes(bytes),
+            Endian::Little => u32::from_le_bytes(bytes),
+        })
+    }
+    fn i32(self, data : &[u8], offset : usize) -> Option<i32>
+    {
+        self.u32(data, offset).map(|x| x as i32)
+    }
+    fn f32(self, data : &[u8], offset : usize) -> Option<f64>
+    {
+        self.u32(data, offset).map(|x| f32::from_bits(x) as f64)
+    }
+    ///blocks start with 2 written in their byte order
+    fn detect(block : &[u8]) -> Self
+    {
+        if Endian::Big.u16(block, 0) == Some(2)
+        {
+            Endian::Big
+        }
+        else
+        {
+            Endian::Little
+        }
+    }
+}
+
+impl FFFCalibration
+{
+    fn parse(block : &[u8]) -> Option<Self>
+    {
+        if block.len() < CAMERA_INFO_MIN_LEN
+        {
+            return None;
+        }
+        let endian = Endian::detect(block);
+        let float = |offset| endian.f32(block, offset);
+        let relative_humidity = float(0x3c)?;
+        Some(Self {
+            emissivity :          float(0x20)?,
+            object_distance :     float(0x24)?,
+            reflected_temp :      float(0x28)?,
+            atmosphere_temp :     float(0x2c)?,
+            window_temp :         float(0x30)?,
+            window_transmission : float(0x34)?,
+            //some cameras store percent
+            relative_humidity :   if relative_humidity > 2.0
+            {
+                relative_humidity / 100.0
+            }
+            else
+            {
+                relative_humidity
+            },
+            planck_r1 :           float(0x58)?,
+            planck_b :            float(0x5c)?,
+            planck_f :            float(0x60)?,
+            atm_alpha :           [float(0x70)?, float(0x74)?],
+            atm_beta :            [float(0x78)?, float(0x7c)?],
+            atm_x :               float(0x80)?,
+            planck_o :            endian.i32(block, 0x308)? as f64,
+            planck_r2 :           float(0x30c)?,
+        })
+    }
+
+    ///converts raw sensor counts to object temperature [°C];
+    ///corrects for emissivity, reflected radiation, atmosphere (on both sides of IR window) & IR window
+    pub fn converter(&self) -> impl Fn(f64) -> f64
+    {
+        let c = *self;
+        //black body radiation at temperature `t` [K] in raw counts
+        let raw = |t : f64| {
+            c.planck_r1 / (c.planck_r2 * ((c.planck_b / t).exp() - c.planck_f)) - c.planck_o
+        };
+        let emissivity = if c.emissivity > 0.0 && c.emissivity <= 1.0
+        {
+            c.emissivity
+        }
+        else
+        {
+            1.0
+        };
+        let window = if c.window_transmission > 0.0 && c.window_transmission <= 1.0
+        {
+            c.window_transmission
+        }
+        else
+        {
+            1.0
+        };
+        //water vapour content & transmission of atmosphere (half of object distance on each side of window)
+        let t_atm = c.atmosphere_temp - KELVIN;
+        let h2o = c.relative_humidity
+            * (1.5587 + 0.06939 * t_atm - 0.00027816 * t_atm.powi(2)
+                + 0.00000068455 * t_atm.powi(3))
+            .exp();
+        let d = (c.object_distance.max(0.0) / 2.0).sqrt();
+        let tau = c.atm_x * (-d * (c.atm_alpha[0] + c.atm_beta[0] * h2o.sqrt())).exp()
+            + (1.0 - c.atm_x) * (-d * (c.atm_alpha[1] + c.atm_beta[1] * h2o.sqrt())).exp();
+        let raw_atm = raw(c.atmosphere_temp);
+        let offset = (1.0 - emissivity) / emissivity * raw(c.reflected_temp)
+            + (1.0 - tau) / emissivity / tau * raw_atm
+            + (1.0 - window) / emissivity / tau / window * raw(c.window_temp)
+            + (1.0 - tau) / emissivity / tau / window / tau * raw_atm;
+        let gain = 1.0 / (emissivity * tau * window * tau);
+        move |counts| {
+            let raw_obj = counts * gain - offset;
+            c.planck_b / (c.planck_r1 / (c.planck_r2 * (raw_obj + c.planck_o)) + c.planck_f).ln()
+                - KELVIN
+        }
+    }
+}
+
+impl FFFFrame
+{
+    ///metadata entries in `TTMetadata` form
+    pub fn entries(&self) -> Vec<(String, String)>
+    {
+        let c = &self.calibration;
+        [
+            ("Camera", self.camera.clone()),
+            ("Emissivity", c.emissivity.to_string()),
+            (
+                "Reflected temperature",
+                format!("{} °C", c.reflected_temp - KELVIN),
+            ),
+            (
+                "Atmospheric temperature",
+                format!("{} °C", c.atmosphere_temp - KELVIN),
+            ),
+            (
+                "Relative humidity",
+                format!("{} %", 100.0 * c.relative_humidity),
+            ),
+            ("Object distance", format!("{} m", c.object_distance)),
+            (
+                "IR window temperature",
+                format!("{} °C", c.window_temp - KELVIN),
+            ),
+            ("IR window transmission", c.window_transmission.to_string()),
+            ("Planck R1", c.planck_r1.to_string()),
+            ("Planck R2", c.planck_r2.to_string()),
+            ("Planck B", c.planck_b.to_string()),
+            ("Planck F", c.planck_f.to_string()),
+            ("Planck O", c.planck_o.to_string()),
+            ("Unit", "°C".to_string()),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+}
+
+//=======================================
+//================Helpers================
+//=======================================
+
+///reads consecutive records from `reader` (should be buffered) & passes their frames to `on_frame`;
+///records without raw data are skipped, truncated last record (interrupted recording) is ignored
+pub fn read_fff_sequence<R : Read>(
+    mut reader : R,
+    mut on_frame : impl FnMut(FFFFrame) -> Result<(), TTLoadError>,
+) -> Result<(), TTLoadError>
+{
+    let mut record = Vec::new();
+    while find_record(&mut reader)?
+    {
+        record.clear();
+        record.extend_from_slice(&FFF_MAGIC);
+        match parse_record(&mut reader, &mut record)
+        {
+            Ok(Some(frame)) => on_frame(frame)?,
+            Ok(None) => (),
+            Err(TTLoadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+///FFF record embedded in APP1 "FLIR" segments of radiometric jpeg
+pub fn fff_from_jpeg(jpeg : &[u8]) -> Result<Vec<u8>, TTLoadError>
+{
+    let mut parts : Vec<(u8, &[u8])> = Vec::new();
+    let mut pos = 2; //skip SOI marker
+    while let (Some(&[0xFF, marker]), Some(len)) = (
+        jpeg.get(pos..pos + 2),
+        Endian::Big.u16(jpeg, pos + 2).map(|len| len as usize),
+    )
+    {
+        //SOS- compressed image data follow, no more metadata
+        if marker == 0xDA
+        {
+            break;
+        }
+        let segment = jpeg
+            .get(pos + 4..pos + 2 + len)
+            .ok_or(TTLoadError::Flir("truncated jpeg segment"))?;
+        //"FLIR\0", 0x01, part index, last part index, data
+        if marker == 0xE1 && segment.starts_with(JPEG_FLIR_SEGMENT) && segment.len() > 8
+        {
+            parts.push((segment[6], &segment[8..]));
+        }
+        pos += 2 + len;
+    }
+    if parts.is_empty()
+    {
+        return Err(TTLoadError::Flir("jpeg contains no radiometric data"));
+    }
+    parts.sort_by_key(|(idx, _)| *idx);
+    Ok(parts
+        .into_iter()
+        .flat_map(|(_, data)| data.iter().copied())
+        .collect())
+}
+
+///skips bytes up to & including next `FFF_MAGIC`; returns false at end of stream
+fn find_record<R : Read>(reader : &mut R) -> io::Result<bool>
+{
+    let mut window = [0u8; 4];
+    let mut byte = [0u8];
+    let mut read = 0;
+    loop
+    {
+        match reader.read(&mut byte)
+        {
+            Ok(0) => return Ok(false),
+            Ok(_) =>
+            {
+                window.rotate_left(1);
+                window[3] = byte[0];
+                read += 1;
+                if read >= FFF_MAGIC.len() && window == FFF_MAGIC
+                {
+                    return Ok(true);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+///reads from `reader` until `record` is `len` bytes long
+fn fill<R : Read>(reader : &mut R, record : &mut Vec<u8>, len : usize) -> io::Result<()>
+{
+    let start = record.len();
+    if start < len
+    {
+        record.resize(len, 0);
+        reader.read_exact(&mut record[start..])?;
+    }
+    Ok(())
+}
+
+///`record` contains already read magic; returns `None` for record without raw data
+fn parse_record<R : Read>(
+    reader : &mut R,
+    record : &mut Vec<u8>,
+) -> Result<Option<FFFFrame>, TTLoadError>
+{
+    fill(reader, record, FFF_HEADER_LEN)?;
+    //header is big endian, unless version does not make sense
+    let valid_version = |endian : Endian| {
+        endian
+            .u32(record, 0x14)
+            .map_or(false, |v| (100..200).contains(&v))
+    };
+    let endian = if valid_version(Endian::Big)
+    {
+        Endian::Big
+    }
+    else if valid_version(Endian::Little)
+    {
+        Endian::Little
+    }
+    else
+    {
+        return Err(TTLoadError::Flir("unknown FFF version"));
+    };
+    let index_offset = endian.u32(record, 0x18).unwrap_or(0) as usize;
+    let entries = endian.u32(record, 0x1c).unwrap_or(0) as usize;
+    let index_end = index_offset + entries * FFF_INDEX_ENTRY_LEN;
+    if entries > FFF_MAX_INDEX_ENTRIES || index_end > FFF_MAX_RECORD_LEN
+    {
+        return Err(TTLoadError::Flir("invalid FFF index"));
+    }
+    fill(reader, record, index_end)?;
+
+    let mut end = index_end;
+    let mut raw : Option<Range<usize>> = None;
+    let mut camera : Option<Range<usize>> = None;
+    for entry in record[index_offset..index_end].chunks_exact(FFF_INDEX_ENTRY_LEN)
+    {
+        //entry: type, subtype, version, id, offset, length, ...
+        let kind = endian.u16(entry, 0).unwrap_or(0);
+        let offset = endian.u32(entry, 12).unwrap_or(0) as usize;
+        let range = offset..offset + endian.u32(entry, 16).unwrap_or(0) as usize;
+        end = end.max(range.end);
+        match kind
+        {
+            FFF_RAW_DATA => raw = Some(range),
+            FFF_CAMERA_INFO => camera = Some(range),
+            _ => (),
+        }
+    }
+    if end > FFF_MAX_RECORD_LEN
+    {
+        return Err(TTLoadError::Flir("invalid FFF index"));
+    }
+    fill(reader, record, end)?;
+
+    let raw = match raw
+    {
+        Some(raw) => &record[raw],
+        None => return Ok(None),
+    };
+    let camera = &record[camera.ok_or(TTLoadError::Flir("record without camera info"))?];
+    let calibration = FFFCalibration::parse(camera)
+        .ok_or(TTLoadError::Flir("camera info without Planck constants"))?;
+    if calibration.planck_r1 == 0.0 || calibration.planck_r2 == 0.0 || calibration.planck_b == 0.0
+    {
+        return Err(TTLoadError::Flir("camera info without Planck constants"));
+    }
+    let (width, height, counts) = raw_counts(raw)?;
+    let convert = calibration.converter();
+    let camera_endian = Endian::detect(camera);
+    Ok(Some(FFFFrame {
+        width,
+        height,
+        temperatures : counts.into_iter().map(|x| convert(x as f64)).collect(),
+        camera : String::from_utf8_lossy(camera.get(0xd4..0xf4).unwrap_or_default())
+            .trim_end_matches('\0')
+            .trim()
+            .to_string(),
+        calibration,
+        //seconds & milliseconds
+        time : camera_endian
+            .u32(camera, 0x384)
+            .zip(camera_endian.u32(camera, 0x388))
+            .filter(|&(s, _)| s > 0)
+            .map(|(s, ms)| s as f64 + 1e-3 * ms as f64),
+    }))
+}
+
+///image size & row-major sensor counts (stored directly or as png)
+fn raw_counts(block : &[u8]) -> Result<(usize, usize, Vec<u16>), TTLoadError>
+{
+    let endian = Endian::detect(block);
+    let (width, height) = match (endian.u16(block, 2), endian.u16(block, 4))
+    {
+        (Some(width), Some(height)) => (width as usize, height as usize),
+        _ => return Err(TTLoadError::Flir("truncated raw data")),
+    };
+    let pixels = block.get(RAW_DATA_PIXELS..).unwrap_or_default();
+    let counts : Vec<u16> = if pixels.starts_with(PNG_SIGNATURE)
+    {
+        let image = image::load_from_memory_with_format(pixels, ImageFormat::Png)?.into_luma16();
+        //FLIR writes little endian values into (big endian) png
+        image.into_raw().into_iter().map(u16::swap_bytes).collect()
+    }
+    else
+    {
+        pixels
+            .chunks_exact(2)
+            .take(width * height)
+            .map(|x| endian.u16(x, 0).unwrap_or(0))
+            .collect()
+    };
+    if counts.len() != width * height
+    {
+        return Err(TTLoadError::Flir("raw data size does not match image size"));
+    }
+    Ok((width, height, counts))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Cursor;
+
+    const WIDTH : usize = 4;
+    const HEIGHT : usize = 3;
+    const TIME : (u32, u32) = (1_600_000_000, 250);
+
+    ///value as stored in f32 field of camera info block
+    fn f(x : f64) -> f64 { x as f32 as f64 }
+    fn calibration() -> FFFCalibration
+    {
+        FFFCalibration {
+            emissivity :          f(0.95),
+            object_distance :     f(1.0),
+            reflected_temp :      f(293.15),
+            atmosphere_temp :     f(293.15),
+            window_temp :         f(293.15),
+            window_transmission : f(1.0),
+            relative_humidity :   f(0.5),
+            planck_r1 :           f(21106.77),
+            planck_r2 :           f(0.012545258),
+            planck_b :            f(1501.0),
+            planck_f :            f(1.0),
+            planck_o :            -7340.0,
+            atm_alpha :           [f(0.006569), f(0.01262)],
+            atm_beta :            [f(-0.002276), f(-0.00667)],
+            atm_x :               f(1.9),
+        }
+    }
+    fn counts() -> Vec<u16> { (0..WIDTH * HEIGHT).map(|i| 17000 + 97 * i as u16).collect() }
+    ///black body radiation at temperature `t` [K] in raw counts
+    fn planck_raw(c : &FFFCalibration, t : f64) -> f64
+    {
+        c.planck_r1 / (c.planck_r2 * ((c.planck_b / t).exp() - c.planck_f)) - c.planck_o
+    }
+
+    ///writes big endian `bytes` in `endian` order
+    fn put<const N: usize>(endian : Endian, data : &mut [u8], offset : usize, mut bytes : [u8; N])
+    {
+        if let Endian::Little = endian
+        {
+            bytes.reverse();
+        }
+        data[offset..offset + N].copy_from_slice(&bytes);
+    }
+    fn camera_block(endian : Endian) -> Vec<u8>
+    {
+        let c = calibration();
+        let mut block = vec![0u8; 0x390];
+        put(endian, &mut block, 0, 2u16.to_be_bytes());
+        let mut float = |offset, x : f64| put(endian, &mut block, offset, (x as f32).to_be_bytes());
+        float(0x20, c.emissivity);
+        float(0x24, c.object_distance);
+        float(0x28, c.reflected_temp);
+        float(0x2c, c.atmosphere_temp);
+        float(0x30, c.window_temp);
+        float(0x34, c.window_transmission);
+        //stored in percent
+        float(0x3c, 100.0 * c.relative_humidity);
+        float(0x58, c.planck_r1);
+        float(0x5c, c.planck_b);
+        float(0x60, c.planck_f);
+        float(0x70, c.atm_alpha[0]);
+        float(0x74, c.atm_alpha[1]);
+        float(0x78, c.atm_beta[0]);
+        float(0x7c, c.atm_beta[1]);
+        float(0x80, c.atm_x);
+        float(0x30c, c.planck_r2);
+        put(endian, &mut block, 0x308, (c.planck_o as i32).to_be_bytes());
+        block[0xd4..0xdb].copy_from_slice(b"TestCam");
+        put(endian, &mut block, 0x384, TIME.0.to_be_bytes());
+        put(endian, &mut block, 0x388, TIME.1.to_be_bytes());
+        block
+    }
+    fn raw_block(endian : Endian, png : bool) -> Vec<u8>
+    {
+        let mut block = vec![0u8; RAW_DATA_PIXELS];
+        put(endian, &mut block, 0, 2u16.to_be_bytes());
+        put(endian, &mut block, 2, (WIDTH as u16).to_be_bytes());
+        put(endian, &mut block, 4, (HEIGHT as u16).to_be_bytes());
+        if png
+        {
+            //FLIR writes little endian values into png
+            let values : Vec<u16> = counts().into_iter().map(u16::swap_bytes).collect();
+            let image = image::ImageBuffer::<image::Luma<u16>, _>::from_raw(
+                WIDTH as u32,
+                HEIGHT as u32,
+                values,
+            )
+            .unwrap();
+            let mut png = Cursor::new(Vec::new());
+            image.write_to(&mut png, ImageFormat::Png).unwrap();
+            block.extend(png.into_inner());
+        }
+        else
+        {
+            for count in counts()
+            {
+                let mut bytes = [0u8; 2];
+                put(endian, &mut bytes, 0, count.to_be_bytes());
+                block.extend(bytes);
+            }
+        }
+        block
+    }
+    ///header, index & blocks of (type, data)
+    fn record(endian : Endian, blocks : &[(u16, Vec<u8>)]) -> Vec<u8>
+    {
+        let mut record = vec![0u8; FFF_HEADER_LEN + blocks.len() * FFF_INDEX_ENTRY_LEN];
+        record[..4].copy_from_slice(&FFF_MAGIC);
+        put(endian, &mut record, 0x14, 101u32.to_be_bytes());
+        put(
+            endian,
+            &mut record,
+            0x18,
+            (FFF_HEADER_LEN as u32).to_be_bytes(),
+        );
+        put(
+            endian,
+            &mut record,
+            0x1c,
+            (blocks.len() as u32).to_be_bytes(),
+        );
+        for (i, (kind, data)) in blocks.iter().enumerate()
+        {
+            let entry = FFF_HEADER_LEN + i * FFF_INDEX_ENTRY_LEN;
+            let offset = record.len() as u32;
+            put(endian, &mut record, entry, kind.to_be_bytes());
+            put(endian, &mut record, entry + 12, offset.to_be_bytes());
+            put(
+                endian,
+                &mut record,
+                entry + 16,
+                (data.len() as u32).to_be_bytes(),
+            );
+            record.extend(data);
+        }
+        record
+    }
+    fn frame_record(endian : Endian, png : bool) -> Vec<u8>
+    {
+        record(
+            endian,
+            &[
+                (FFF_RAW_DATA, raw_block(endian, png)),
+                (FFF_CAMERA_INFO, camera_block(endian)),
+            ],
+        )
+    }
+    fn read_frames(sequence : &[u8]) -> Vec<FFFFrame>
+    {
+        let mut frames = Vec::new();
+        read_fff_sequence(sequence, |frame| {
+            frames.push(frame);
+            Ok(())
+        })
+        .unwrap();
+        frames
+    }
+    fn check_frame(frame : &FFFFrame)
+    {
+        assert_eq!((frame.width, frame.height), (WIDTH, HEIGHT));
+        let convert = calibration().converter();
+        for (&temperature, count) in frame.temperatures.iter().zip(counts())
+        {
+            assert!((temperature - convert(count as f64)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn reads_big_and_little_endian_records()
+    {
+        for endian in [Endian::Big, Endian::Little]
+        {
+            let frames = read_frames(&frame_record(endian, false));
+            assert_eq!(frames.len(), 1);
+            check_frame(&frames[0]);
+        }
+    }
+
+    #[test]
+    fn reads_png_raw_counts()
+    {
+        let frames = read_frames(&frame_record(Endian::Little, true));
+        assert_eq!(frames.len(), 1);
+        check_frame(&frames[0]);
+    }
+
+    #[test]
+    fn parses_camera_info()
+    {
+        let frame = read_frames(&frame_record(Endian::Big, false)).remove(0);
+        assert_eq!(frame.calibration, calibration());
+        assert_eq!(frame.camera, "TestCam");
+        let time = TIME.0 as f64 + 1e-3 * TIME.1 as f64;
+        assert!((frame.time.unwrap() - time).abs() < 1e-6);
+        assert!(frame
+            .entries()
+            .contains(&("Camera".to_string(), "TestCam".to_string())));
+    }
+
+    #[test]
+    fn converter_inverts_planck_formula()
+    {
+        //ideal conditions: no atmosphere, window or reflections
+        let ideal = FFFCalibration {
+            emissivity : 1.0,
+            object_distance : 0.0,
+            window_transmission : 1.0,
+            ..calibration()
+        };
+        let convert = ideal.converter();
+        for t in [-20.0, 0.0, 25.0, 80.0, 150.0]
+        {
+            let counts = planck_raw(&ideal, t + KELVIN);
+            assert!((convert(counts) - t).abs() < 1e-6, "{} °C", t);
+        }
+        //thermal equilibrium: object, surroundings, atmosphere & window at same temperature
+        //give black body counts regardless of emissivity & transmissions
+        for t in [0.0, 40.0]
+        {
+            let equilibrium = FFFCalibration {
+                object_distance : 10.0,
+                window_transmission : 0.8,
+                reflected_temp : t + KELVIN,
+                atmosphere_temp : t + KELVIN,
+                window_temp : t + KELVIN,
+                ..calibration()
+            };
+            let counts = planck_raw(&equilibrium, t + KELVIN);
+            assert!(
+                (equilibrium.converter()(counts) - t).abs() < 1e-6,
+                "{} °C",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_last_record_is_ignored()
+    {
+        let record = frame_record(Endian::Big, false);
+        let mut sequence = [record.clone(), record.clone()].concat();
+        sequence.extend_from_slice(&record[..record.len() - 5]);
+        let frames = read_frames(&sequence);
+        assert_eq!(frames.len(), 2);
+        frames.iter().for_each(check_frame);
+    }
+
+    #[test]
+    fn jpeg_parts_are_joined_in_index_order()
+    {
+        let record = frame_record(Endian::Little, false);
+        let parts : Vec<&[u8]> = record.chunks(record.len() / 3 + 1).collect();
+        let segment = |marker : u8, content : &[u8]| {
+            let mut segment = vec![0xFF, marker];
+            segment.extend(((content.len() + 2) as u16).to_be_bytes());
+            segment.extend(content);
+            segment
+        };
+        let flir = |index : usize| {
+            let header = [JPEG_FLIR_SEGMENT, &[0x01, index as u8, 2][..]].concat();
+            segment(0xE1, &[header.as_slice(), parts[index]].concat())
+        };
+        //SOI, JFIF, parts out of order, SOS
+        let jpeg = [
+            vec![0xFF, 0xD8],
+            segment(0xE0, b"JFIF\0"),
+            flir(2),
+            flir(0),
+            flir(1),
+            segment(0xDA, &[0; 4]),
+        ]
+        .concat();
+        assert_eq!(fff_from_jpeg(&jpeg).unwrap(), record);
+        assert!(fff_from_jpeg(&[0xFF, 0xD8, 0xFF, 0xDA, 0, 2]).is_err());
+    }
+}
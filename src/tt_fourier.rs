@@ -17,14 +17,17 @@ use std::f64::consts::PI;
 use std::mem;
 use std::mem::MaybeUninit;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
 
 use crate::gap_window::GAPWin;
 use crate::tt_common::*;
+///number of X columns transformed at once (cancellation is checked between chunks)
+const FFT_CHUNK_LEN : usize = 16;
 pub struct TTFourier
 {
     time_len :            usize,
     time_len_wo_padding : usize,
+    ///apodisation window applied before transform
+    window :              GAPWin,
     data :                Array3<Complex64>,
 }
 #[derive(Clone)]
@@ -32,6 +35,7 @@ struct TTFourierUninit
 {
     _time_len :            usize,
     _time_len_wo_padding : usize,
+    _window :              GAPWin,
     data :                 Array3<MaybeUninit<Complex<f64>>>,
 }
 
@@ -41,6 +45,7 @@ impl TTFourierUninit
         shape : (usize, usize, usize),
         time_len : usize,
         time_len_wo_padding : usize,
+        window : GAPWin,
     ) -> [TTFourierUninit; N]
     {
         // Create an uninitialized array of `MaybeUninit`. The `assume_init` is
@@ -58,6 +63,7 @@ impl TTFourierUninit
                 data :                 Array3::uninit(shape),
                 _time_len :            time_len,
                 _time_len_wo_padding : time_len_wo_padding,
+                _window :              window,
             });
         }
 
@@ -71,7 +77,12 @@ impl TTFourierUninit
 }
 impl TTFourier
 {
-    pub fn new(input : &TTInputData, file_state : Arc<AtomicFileState>) -> Option<TTFourier>
+    ///returns `None` if processing was cancelled
+    pub fn new(
+        input : &TTInputData,
+        params : &TTProcessingParams,
+        cancel : &TTCancelToken,
+    ) -> Option<TTFourier>
     {
         let mut shape_raw = input.data.dim();
         let mut fft_handler = R2cFftHandler::<f64>::new(shape_raw.2);
@@ -81,26 +92,67 @@ impl TTFourier
             data : Array3::zeros(shape_raw),
             time_len,
             time_len_wo_padding : input.frames,
+            window : params.window.gap_win(),
         };
         let mut windowed_data = input.data.to_owned();
-        let window = GAPWin::KAISER_17_OPT.window(input.frames);
-        windowed_data
-            .lanes_mut(AXIS_T)
-            .into_iter()
-            .into_par_iter()
-            .for_each(|mut lane| lane.iter_mut().zip(&window).for_each(|(i, w)| *i *= w));
-        ndfft_r2c_par(&windowed_data, &mut fourier.data, &mut fft_handler, 2);
-
-        if FileState::ProcessingFourier == file_state.load(Ordering::Relaxed)
+        let window = fourier.window.window(input.frames);
+        if let Some(reference) = params.diff_reference
         {
-            Some(fourier)
+            windowed_data
+                .lanes_mut(AXIS_T)
+                .into_iter()
+                .into_par_iter()
+                .for_each(|mut lane| {
+                    if cancel.is_cancelled()
+                    {
+                        return;
+                    }
+                    //padding frames are left untouched (zeroed)
+                    let r = lane[reference];
+                    lane.iter_mut()
+                        .zip(&window)
+                        .for_each(|(i, w)| *i = (*i - r) * w)
+                });
         }
         else
         {
-            None
+            windowed_data
+                .lanes_mut(AXIS_T)
+                .into_iter()
+                .into_par_iter()
+                .for_each(|mut lane| {
+                    if !cancel.is_cancelled()
+                    {
+                        lane.iter_mut().zip(&window).for_each(|(i, w)| *i *= w)
+                    }
+                });
+        }
+        for (input_chunk, mut output_chunk) in windowed_data
+            .axis_chunks_iter(Axis(0), FFT_CHUNK_LEN)
+            .zip(fourier.data.axis_chunks_iter_mut(Axis(0), FFT_CHUNK_LEN))
+        {
+            if cancel.is_cancelled()
+            {
+                return None;
+            }
+            ndfft_r2c_par(&input_chunk, &mut output_chunk, &mut fft_handler, 2);
         }
+        if cancel.is_cancelled()
+        {
+            return None;
+        }
+        Some(fourier)
     }
 
+    ///same as `new()`, but cannot be cancelled
+    pub fn transform(input : &TTInputData, params : &TTProcessingParams) -> TTFourier
+    {
+        Self::new(input, params, &Default::default()).unwrap() //default token is never cancelled
+    }
+
+    ///Fourier transform along time axis (X, Y, F)
+    pub fn data(&self) -> &Array3<Complex64> { &self.data }
+
     pub fn snapshot(&self, params : &ViewMode, settings : &GlobalSettings) -> Array2<f64>
     {
         let settings_axis = params.get_settings_axes()[0] as usize;
@@ -138,7 +190,7 @@ impl TTFourier
         }
     }
 
-    fn integrals_dft<const N: usize>(&self) -> [TTFourier; N]
+    fn integrals_dft<const N: usize>(&self, cancel : &TTCancelToken) -> Option<[TTFourier; N]>
     {
         // each component of DFT (X_k) encodes ampltude and phase of cos(2*PI*f_k*t)+i*sin(2*PI*f_k*t) [==e^(2*i*PI*f_k*t)]
         // where f_k = k/N & k-> DFT component index & N-> Number of DFT components
@@ -157,7 +209,7 @@ impl TTFourier
         let shape = self.data.dim();
 
         let mut uninit_data : [TTFourierUninit; N] =
-            TTFourierUninit::new(shape, self.time_len, self.time_len_wo_padding);
+            TTFourierUninit::new(shape, self.time_len, self.time_len_wo_padding, self.window);
         // exec_time.stop_print("uninit");
         // exec_time.start();
 
@@ -208,6 +260,12 @@ impl TTFourier
                     .into_par_iter()
                     .zip(self.data.lanes(AXIS_T).into_iter())
                     .for_each(|(mut oarray, iarray)| {
+                        if cancel.is_cancelled()
+                        {
+                            //result is discarded, but still has to be initialized
+                            oarray.fill(MaybeUninit::new(Complex64::default()));
+                            return;
+                        }
                         oarray
                             .as_slice_memory_order_mut()
                             .unwrap()
@@ -239,6 +297,11 @@ impl TTFourier
                     .into_par_iter()
                     .zip(self.data.lanes(AXIS_T).into_iter())
                     .for_each(|(mut oarray, iarray)| {
+                        if cancel.is_cancelled()
+                        {
+                            oarray.fill(MaybeUninit::new(Complex64::default()));
+                            return;
+                        }
                         oarray
                             .as_slice_memory_order_mut()
                             .unwrap()
@@ -263,6 +326,10 @@ impl TTFourier
         mem::forget(uninit_data);
         // exec_time.stop_print("cast");
         // exec_time.start();
+        if cancel.is_cancelled()
+        {
+            return None;
+        }
 
         res.par_iter_mut().for_each(|x| {
             let fill = Complex64::new(0.0, 0.0);
@@ -270,42 +337,72 @@ impl TTFourier
         });
         // exec_time.stop_print("end");
 
-        res
+        Some(res)
     }
 
     pub fn inverse_transform(&self) -> Array3<f64>
+    {
+        self.inverse_transform_cancellable(&Default::default())
+            .unwrap() //default token is never cancelled
+    }
+    fn inverse_transform_cancellable(&self, cancel : &TTCancelToken) -> Option<Array3<f64>>
     {
         let mut shape = self.data.dim();
         shape.2 = self.time_len;
         let mut handler = R2cFftHandler::<f64>::new(shape.2);
         let mut output = Array3::zeros(shape);
-        ndifft_r2c_par(&self.data, &mut output, &mut handler, 2);
+        for (input_chunk, mut output_chunk) in self
+            .data
+            .axis_chunks_iter(Axis(0), FFT_CHUNK_LEN)
+            .zip(output.axis_chunks_iter_mut(Axis(0), FFT_CHUNK_LEN))
+        {
+            if cancel.is_cancelled()
+            {
+                return None;
+            }
+            ndifft_r2c_par(&input_chunk, &mut output_chunk, &mut handler, 2);
+        }
         output.slice_axis_inplace(AXIS_T, Slice::from(..self.time_len_wo_padding));
-        output
+        Some(output)
     }
 
-    pub fn integrals<const N: usize>(&self) -> [Array3<f64>; N]
+    ///returns `None` if processing was cancelled
+    pub fn integrals<const N: usize>(&self, cancel : &TTCancelToken) -> Option<[Array3<f64>; N]>
     {
         // let mut exec_time = ExecutionTimeMeas::new("exec_time_fourier.txt");
         // exec_time.start();
-        let temp = self.integrals_dft();
+        let temp = self.integrals_dft(cancel)?;
         // exec_time.stop_print("initial");
         // exec_time.start();
-        let mut ret = temp.map(|x| x.inverse_transform());
+        let ret = temp.map(|x| x.inverse_transform_cancellable(cancel));
+        if ret.iter().any(Option::is_none)
+        {
+            return None;
+        }
+        let mut ret = ret.map(Option::unwrap);
         // exec_time.stop_print("ifft");
         //remove window from signal
         // this is not fully correct!!!, as integral is not (fi*w).(fi*x), but fi*(w.x), where fi is operation taken in Fourier domain to integrate and '.' is multiplication, w is window, x is signal
         ret.par_iter_mut().enumerate().for_each(|(i, integral3d)| {
-            let win = GAPWin::KAISER_17_OPT.integrated_window(self.time_len_wo_padding, i);
+            let win = self.window.integrated_window(self.time_len_wo_padding, i);
             let iwin = win.map(|w| 1. / w);
             integral3d
                 .lanes_mut(AXIS_T)
                 .into_iter()
                 .into_par_iter()
-                .for_each(|mut lane| lane.iter_mut().zip(&iwin).for_each(|(i, w)| *i *= w))
+                .for_each(|mut lane| {
+                    if !cancel.is_cancelled()
+                    {
+                        lane.iter_mut().zip(&iwin).for_each(|(i, w)| *i *= w)
+                    }
+                })
         });
 
         // exec_time.stop_print("de-window");
-        ret
+        if cancel.is_cancelled()
+        {
+            return None;
+        }
+        Some(ret)
     }
 }
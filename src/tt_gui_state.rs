@@ -1,7 +1,10 @@
 use crate::tt_backend_state::*;
 use crate::tt_common::*;
 use crate::tt_file::TTFile;
-use crate::wavelet::WaveletType;
+use crate::tt_plot::TTPixelPlots;
+use crate::tt_tsr::TSR_MAX_DEGREE;
+use crate::wavelet::{AtomicWaveletType, WaveletType};
+use egui::plot::{Legend, Line, Plot, PlotPoints};
 use egui::{
     Color32,
     ColorImage,
@@ -9,6 +12,7 @@ use egui::{
     DragValue,
     Image,
     Pos2,
+    ProgressBar,
     Rect,
     RichText,
     Sense,
@@ -19,7 +23,8 @@ use egui::{
 };
 use egui_extras::{Column, TableBuilder};
 use ndarray::IntoDimension;
-use parking_lot::{Condvar, Mutex};
+use parking_lot::{Condvar, Mutex, RwLock};
+use rfd::FileDialog;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
@@ -34,26 +39,42 @@ use Ordering::Relaxed;
 
 pub struct TTViewGUI
 {
-    state :     Arc<AtomicTTViewState>,
-    image :     tribuf::Output<Thermogram>,
-    view_mode : Arc<ViewMode>,
-    settings :  Arc<GlobalSettings>,
+    state :        Arc<AtomicTTViewState>,
+    image :        tribuf::Output<Thermogram>,
+    view_mode :    Arc<ViewMode>,
+    settings :     Arc<GlobalSettings>,
+    ///error of last png export, if it failed
+    export_error : Option<String>,
+}
+
+///curves at crossection pixel
+pub struct TTPlotGUI
+{
+    state :    Arc<AtomicTTViewState>,
+    plots :    tribuf::Output<TTPixelPlots>,
+    wavelet :  Arc<AtomicWaveletType>,
+    settings : Arc<GlobalSettings>,
 }
 
 pub struct TTFileGUI
 {
     state : Arc<AtomicFileState>,
     path :  tribuf::Input<Option<TTFile>>,
+    info :  Arc<RwLock<TTFileInfo>>,
 }
 
 pub struct TTStateGUI
 {
     views :          [TTViewGUI; 4],
+    plot :           TTPlotGUI,
+    show_plots :     bool,
     changed :        Arc<(Mutex<bool>, Condvar)>,
     stop_flag :      Arc<AtomicBool>,
     file :           TTFileGUI,
     backend_handle : Option<JoinHandle<()>>,
     settings :       Arc<GlobalSettings>,
+    ///error of last custom gradient loading, if it failed
+    gradient_error : Option<String>,
 }
 
 //=======================================
@@ -199,7 +220,9 @@ impl ViewMode
             match self.domain.load(Ordering::Relaxed)
             {
                 ViewModeDomain::TimeView =>
-                {}
+                {
+                    changed |= self.differential.show_switchable(ui, "diff");
+                }
                 ViewModeDomain::FourierView =>
                 {
                     ui.style_mut().wrap = Some(false);
@@ -215,6 +238,69 @@ impl ViewMode
                     ui.label("| wavelet:");
                     changed |= atomicCombobox!(self.wavelet, ui);
                 }
+                ViewModeDomain::PCTView => (),
+                ViewModeDomain::LockInView =>
+                {
+                    ui.style_mut().wrap = Some(false);
+                    ui.label("| mode:");
+                    changed |= atomicCombobox!(self.display_mode, ui);
+                    ui.style_mut().wrap = Some(false);
+                    ui.label("| method:");
+                    changed |= atomicCombobox!(self.lockin_method, ui);
+                    let nyquist = 0.5 * *global.frame_rate.read();
+                    ui.label("| f [Hz]:");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut *self.lockin_freq.write())
+                                .speed(0.01)
+                                .clamp_range(0.001..=nyquist),
+                        )
+                        .changed();
+                }
+                ViewModeDomain::TSRView =>
+                {
+                    ui.style_mut().wrap = Some(false);
+                    ui.label("| mode:");
+                    changed |= atomicCombobox!(self.tsr_mode, ui);
+                    let mut degree = RangedVal {
+                        val : self.tsr_degree.load(Ordering::Relaxed),
+                        min : 1,
+                        max : TSR_MAX_DEGREE,
+                    };
+                    ui.label("| degree:");
+                    if degree.show(ui)
+                    {
+                        self.tsr_degree.store(degree.val, Ordering::Relaxed);
+                        changed = true;
+                    }
+                }
+                ViewModeDomain::CompositeWaveletView =>
+                {
+                    ui.style_mut().wrap = Some(false);
+                    ui.label("| mode:");
+                    changed |= atomicCombobox!(self.composite_mode, ui);
+                    ui.style_mut().wrap = Some(false);
+                    ui.label("| wavelet:");
+                    changed |= atomicCombobox!(self.wavelet, ui);
+                    /*scale band DragValues (scales displayed 1-based)*/
+                    let max = global.full_size.read()[TTAxis::S as usize] + 1;
+                    let mut scale_band = self.scale_band.write();
+                    let mut band_min = RangedVal {
+                        val : scale_band[0] + 1,
+                        min : 1,
+                        max : scale_band[1] + 1,
+                    };
+                    let mut band_max = RangedVal {
+                        val : scale_band[1] + 1,
+                        min : band_min.val,
+                        max,
+                    };
+                    ui.label("| scales:");
+                    changed |= band_min.show(ui);
+                    ui.label("-");
+                    changed |= band_max.show(ui);
+                    *scale_band = [band_min.val - 1, band_max.val - 1];
+                }
             }
             /*position DragValues*/
             {
@@ -231,28 +317,22 @@ impl ViewMode
                 for &axis in settings_axes
                 {
                     let uaxis = axis as usize;
-                    let mut ranged_val = if axis == TTAxis::S
-                    {
-                        RangedVal {
-                            val : position[uaxis] + 1,
-                            min : 1,
-                            max : full_size[uaxis] + 1,
-                        }
-                    }
-                    else
-                    {
-                        RangedVal {
-                            val : position[uaxis],
-                            min : 0,
-                            max : full_size[uaxis],
-                        }
+                    let offset = axis.display_offset();
+                    let mut ranged_val = RangedVal {
+                        val : position[uaxis] + offset,
+                        min : offset,
+                        max : full_size[uaxis] + offset,
                     };
                     ui.label(Into::<&str>::into(axis));
                     changed |= ranged_val.show(ui);
-                    position[uaxis] = ranged_val.val;
-                    if axis == TTAxis::S
+                    position[uaxis] = ranged_val.val - offset;
+                    if let Some((value, unit)) = global.physical_position(
+                        axis,
+                        position[uaxis],
+                        self.wavelet.load(Ordering::Relaxed),
+                    )
                     {
-                        position[uaxis] -= 1;
+                        ui.label(format_physical(value, unit));
                     }
                 }
                 if self.bind_position.load(Ordering::Relaxed)
@@ -260,6 +340,26 @@ impl ViewMode
                     *global.crossection.write() = *position;
                 }
             }
+            ui.label("| colors:");
+            changed |= atomicCombobox!(self.scale_mode, ui);
+            match self.scale_mode.load(Ordering::Relaxed)
+            {
+                ScaleMode::Fixed => changed |= show_range(ui, &mut self.scale_range.write()),
+                ScaleMode::Percentile =>
+                {
+                    ui.label("clip %:");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut *self.clip_percent.write())
+                                .speed(0.1)
+                                .clamp_range(0.0..=49.0),
+                        )
+                        .changed();
+                }
+                _ => (),
+            }
+            changed |= atomicCombobox!(self.color_map, ui);
+            changed |= self.reverse_colors.show_switchable(ui, "reverse");
             changed |= self.denoise.show_switchable(ui, "denoise");
         });
         changed
@@ -303,6 +403,15 @@ impl Thermogram
         };
         let responce = ui.horizontal_centered(|ui| {
             let img_rsp = ui.add(Image::new(self.image.id(), size).sense(Sense::click()));
+            let img_rsp = match img_rsp.hover_pos()
+            {
+                Some(pointer) =>
+                {
+                    let pointer = (pointer - img_rsp.rect.min) / img_rsp.rect.size();
+                    img_rsp.on_hover_ui_at_pointer(|ui| self.show_readout(ui, pointer))
+                }
+                None => img_rsp,
+            };
             let size = img_rsp.rect.size();
             let mut roi_x = settings.get_roi(view_axes[0]);
             let mut roi_y = settings.get_roi(view_axes[1]);
@@ -424,7 +533,7 @@ impl Thermogram
             ui.vertical(|ui| {
                 ui.add_space(6.0);
                 ui.image(
-                    self.legend.grad_legend().id(),
+                    self.legend.grad_legend(ui.ctx()).id(),
                     Vec2 {
                         x : 10.0,
                         y : size.y - 10.0,
@@ -467,6 +576,47 @@ impl Thermogram
         });
         (retval, responce)
     }
+    ///coordinates (index & physical) & value before color mapping at `pointer` (relative position within image)
+    fn show_readout(&self, ui : &mut egui::Ui, pointer : Vec2) -> ()
+    {
+        let raw = &self.raw;
+        let dim = raw.data.dim();
+        if dim.0 == 0 || dim.1 == 0
+        {
+            return;
+        }
+        let index = [
+            ((pointer.x * dim.0 as f32) as usize).min(dim.0 - 1),
+            ((pointer.y * dim.1 as f32) as usize).min(dim.1 - 1),
+        ];
+        let wavelet = self.view_mode.wavelet.load(Relaxed);
+        egui::Grid::new("readout").show(ui, |ui| {
+            for i in 0..2
+            {
+                let axis = raw.axes[i];
+                let position = raw.offset[i] + index[i];
+                ui.label(Into::<char>::into(axis).to_string());
+                ui.label((position + axis.display_offset()).to_string());
+                match self.settings.physical_position(axis, position, wavelet)
+                {
+                    Some((value, unit)) => ui.label(format_physical(value, unit)),
+                    None => ui.label(""),
+                };
+                ui.end_row();
+            }
+            let unit = if self.legend == TTGradients::Phase
+            {
+                "rad"
+            }
+            else
+            {
+                ""
+            };
+            ui.label("value");
+            ui.label(format_physical(raw.data[index], unit));
+            ui.end_row();
+        });
+    }
 }
 
 fn tt_view_new(
@@ -485,10 +635,11 @@ fn tt_view_new(
     let state = Arc::new(AtomicTTViewState::new(TTViewState::Invalid));
     (
         TTViewGUI {
-            state :     state.clone(),
-            image :     image_output,
-            view_mode : aparams.clone(),
-            settings :  settings.clone(),
+            state :        state.clone(),
+            image :        image_output,
+            view_mode :    aparams.clone(),
+            settings :     settings.clone(),
+            export_error : None,
         },
         TTViewBackend {
             state :            state,
@@ -501,13 +652,168 @@ fn tt_view_new(
     )
 }
 
+fn tt_plot_new(settings : Arc<GlobalSettings>) -> (TTPlotGUI, TTPlotBackend)
+{
+    let (plots_input, plots_output) = triple_buffer(&TTPixelPlots::default());
+    let state = Arc::new(AtomicTTViewState::new(TTViewState::Invalid));
+    let wavelet = Arc::new(AtomicWaveletType::new(WaveletType::Morlet));
+    (
+        TTPlotGUI {
+            state :    state.clone(),
+            plots :    plots_output,
+            wavelet :  wavelet.clone(),
+            settings : settings.clone(),
+        },
+        TTPlotBackend {
+            state,
+            plots : plots_input,
+            wavelet,
+            settings,
+        },
+    )
+}
+
+///min & max DragValues; returns `true` if changed
+fn show_range(ui : &mut egui::Ui, range : &mut [f64; 2]) -> bool
+{
+    let [min, max] = *range;
+    let speed = ((max - min).abs() * 0.005).max(1e-3);
+    let mut changed = ui
+        .add(
+            DragValue::new(&mut range[0])
+                .speed(speed)
+                .clamp_range(f64::MIN..=max),
+        )
+        .changed();
+    ui.label("-");
+    changed |= ui
+        .add(
+            DragValue::new(&mut range[1])
+                .speed(speed)
+                .clamp_range(min..=f64::MAX),
+        )
+        .changed();
+    changed
+}
+
+///line plot of `curve` ([x, y] points); x axes of plots in same `link` group move together
+fn show_curve(
+    ui : &mut egui::Ui,
+    name : &str,
+    curve : &[[f64; 2]],
+    size : Vec2,
+    link : &'static str,
+) -> ()
+{
+    Plot::new(name)
+        .width(size.x)
+        .height(size.y)
+        .legend(Legend::default())
+        .link_axis(link, true, false)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(PlotPoints::new(curve.to_vec())).name(name))
+        });
+}
+
 //=======================================
 //============Implementations============
 //=======================================
 
-impl TTViewGUI
+impl TTPlotGUI
 {
+    ///marks plots outdated (if there are any)
+    fn changed(&self) -> ()
+    {
+        if self.state.load(Relaxed) != TTViewState::Invalid
+        {
+            self.state.store(TTViewState::Changed, Relaxed);
+        }
+    }
+    ///returns `true` if plot params changed
     pub fn show(&mut self, ui : &mut egui::Ui) -> bool
+    {
+        let mut changed = false;
+        ui.vertical(|ui| {
+            let state = self.state.load(Relaxed);
+            if state == TTViewState::Invalid
+            {
+                ui.set_enabled(false);
+            }
+            let plots = self.plots.read();
+            let [x, y, s] = plots.position;
+            ui.horizontal(|ui| {
+                ui.label(format!("pixel X: {} Y: {}", x, y));
+                ui.label("| wavelet:");
+                changed |= atomicCombobox!(self.wavelet, ui);
+                ui.label(format!("| scale: {}", s + TTAxis::S.display_offset()));
+                if let Some((value, unit)) =
+                    self.settings
+                        .physical_position(TTAxis::S, s, self.wavelet.load(Relaxed))
+                {
+                    ui.label(format_physical(value, unit));
+                }
+                if state == TTViewState::Processing || state == TTViewState::Changed
+                {
+                    ui.spinner();
+                    ui.ctx().request_repaint(); //faster next screen refresh, when waiting for new plots
+                }
+            });
+            let spacing = ui.spacing().item_spacing;
+            let column = Vec2::new(
+                (ui.available_width() - 2.0 * spacing.x) / 3.0,
+                ui.available_height(),
+            );
+            let half = Vec2::new(column.x, (column.y - spacing.y) / 2.0);
+            ui.horizontal(|ui| {
+                show_curve(
+                    ui,
+                    "temperature (t [s])",
+                    &plots.temperature,
+                    column,
+                    "plot_time",
+                );
+                ui.vertical(|ui| {
+                    show_curve(
+                        ui,
+                        "Fourier magnitude (f [Hz])",
+                        &plots.fourier_magnitude,
+                        half,
+                        "plot_freq",
+                    );
+                    show_curve(
+                        ui,
+                        "Fourier phase [rad] (f [Hz])",
+                        &plots.fourier_phase,
+                        half,
+                        "plot_freq",
+                    );
+                });
+                ui.vertical(|ui| {
+                    show_curve(
+                        ui,
+                        "wavelet magnitude (t [s])",
+                        &plots.wavelet_magnitude,
+                        half,
+                        "plot_time",
+                    );
+                    show_curve(
+                        ui,
+                        "wavelet phase [rad] (t [s])",
+                        &plots.wavelet_phase,
+                        half,
+                        "plot_time",
+                    );
+                });
+            });
+        });
+        changed
+    }
+}
+
+impl TTViewGUI
+{
+    ///`source`- input file path, stored in exported view description
+    pub fn show(&mut self, ui : &mut egui::Ui, source : &str) -> bool
     {
         let mut retval = false;
         ui.vertical(|ui| {
@@ -516,13 +822,51 @@ impl TTViewGUI
                 //grey out & block interactive elements of this view
                 ui.set_enabled(false);
             }
-            if self.view_mode.controls(&self.settings, ui)
+            let mut export = false;
+            let gram = self.image.read();
+            ui.horizontal(|ui| {
+                export = ui
+                    .button("💾")
+                    .on_hover_text(
+                        "save view as .png (with legend & _params.txt view parameters) or raw \
+                         values as .csv/.txt/.npy",
+                    )
+                    .clicked();
+                if let Some(err) = &self.export_error
+                {
+                    ui.label(RichText::new("!").color(Color32::RED).strong())
+                        .on_hover_text(err);
+                }
+                let scale_mode = self.view_mode.scale_mode.load(Ordering::Relaxed);
+                if self.view_mode.controls(&self.settings, ui)
+                {
+                    if scale_mode != ScaleMode::Fixed
+                        && self.view_mode.scale_mode.load(Ordering::Relaxed) == ScaleMode::Fixed
+                    {
+                        //fixed range starts from currently displayed values
+                        if let Some(range) = gram.raw.range()
+                        {
+                            *self.view_mode.scale_range.write() = range;
+                        }
+                    }
+                    //params changed by user
+                    retval = true;
+                    self.state.store(TTViewState::Changed, Ordering::Relaxed);
+                }
+            });
+            if export && self.state.load(Ordering::Relaxed) == TTViewState::Valid
             {
-                //params changed by user
-                retval = true;
-                self.state.store(TTViewState::Changed, Ordering::Relaxed);
+                if let Some(path) = FileDialog::new()
+                    .add_filter("PNG image", &["png"])
+                    .add_filter("CSV raw values", &["csv"])
+                    .add_filter("TXT raw values", &["txt"])
+                    .add_filter("NumPy raw values", &["npy"])
+                    .set_file_name("view.png")
+                    .save_file()
+                {
+                    self.export_error = gram.export(&path, source).err().map(|e| e.to_string());
+                }
             }
-            let gram = self.image.read();
             ui.with_layout(
                 egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
                 |ui| {
@@ -551,11 +895,53 @@ impl TTViewGUI
     }
 }
 
+impl TTFileGUI
+{
+    ///info label with file metadata on hover (if any is available)
+    fn show_metadata(&self, ui : &mut egui::Ui) -> ()
+    {
+        let info = self.info.read();
+        let metadata = &info.metadata;
+        if metadata.is_empty()
+        {
+            return;
+        }
+        ui.label("ℹ").on_hover_ui(|ui| {
+            egui::Grid::new("file_metadata")
+                .striped(true)
+                .show(ui, |ui| {
+                    if let Some(frame_rate) = metadata.frame_rate
+                    {
+                        ui.label("frame rate [Hz]");
+                        ui.label(format!("{:.3}", frame_rate));
+                        ui.end_row();
+                    }
+                    if let Some(&duration) = metadata.timestamps.last()
+                    {
+                        ui.label("duration [s]");
+                        ui.label(format!("{:.3}", duration));
+                        ui.end_row();
+                    }
+                    if let (Some(name), Some(hash)) = (&metadata.source_name, metadata.source_hash)
+                    {
+                        ui.label("converted from");
+                        ui.label(format!("{} (hash {:016x})", name, hash));
+                        ui.end_row();
+                    }
+                    for (key, value) in metadata.entries.iter()
+                    {
+                        ui.label(key);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
 impl TTStateGUI
 {
     pub fn new(ctx : &egui::Context, file : Option<TTFile>) -> Self
     {
-        TTGradients::init_grad(ctx);
         let default_view_params = [
             ViewMode::new(
                 ViewModeDomain::TimeView,
@@ -582,6 +968,7 @@ impl TTStateGUI
         // let (mut views_gui, mut views_backend) : ([TTViewGUI; 4], [TTViewBackend; 4]) =
         let [(g1,b1),(g2,b2),(g3,b3),(g4,b4)] //: [(TTViewGUI, TTViewBackend); 4] 
         = default_view_params.map( |x| tt_view_new("TTParams",x, ctx,settings.clone()));
+        let (plot_gui, plot_backend) = tt_plot_new(settings.clone());
 
         let (path_gui, path_backend);
         let state;
@@ -598,22 +985,29 @@ impl TTStateGUI
 
         let changed = Arc::new((Mutex::new(false), Condvar::new()));
         let stop_flag = Arc::new(AtomicBool::new(false));
+        let info = Arc::new(RwLock::new(TTFileInfo::default()));
         TTStateGUI {
             views :          [g1, g2, g3, g4],
+            plot :           plot_gui,
+            show_plots :     false,
             changed :        changed.clone(),
             stop_flag :      stop_flag.clone(),
             file :           TTFileGUI {
                 state : state.clone(),
                 path :  path_gui,
+                info :  info.clone(),
             },
             settings :       settings.clone(),
+            gradient_error : None,
             backend_handle : Some(thread::spawn(move || {
                 let backend_state = TTStateBackend::new(
                     [b1, b2, b3, b4],
+                    plot_backend,
                     changed,
                     stop_flag,
                     state,
                     path_backend,
+                    info,
                     settings.clone(),
                 );
                 backend_state.run();
@@ -634,6 +1028,9 @@ impl TTStateGUI
         self.views
             .iter()
             .for_each(|view| view.state.store(TTViewState::Invalid, Ordering::Relaxed));
+        self.plot
+            .state
+            .store(TTViewState::Invalid, Ordering::Relaxed);
         //"send" updated path to backend
         self.file.path.write(path.clone());
         //update new gui working buffer
@@ -641,6 +1038,28 @@ impl TTStateGUI
         self.file.state.store(FileState::New, Ordering::Relaxed);
         self.notify_backend()
     }
+    ///restart Fourier & wavelet processing of already loaded file (eg. after processing settings change)
+    pub fn reprocess(&mut self) -> ()
+    {
+        match self.file.state.load(Ordering::Relaxed)
+        {
+            FileState::ProcessingFourier
+            | FileState::ProcessingWavelet
+            | FileState::ReadySaving
+            | FileState::Ready =>
+            {
+                self.views.iter().for_each(|view| {
+                    view.state.store(TTViewState::Changed, Ordering::Relaxed);
+                });
+                self.plot.changed();
+                self.file
+                    .state
+                    .store(FileState::Reprocessing, Ordering::Relaxed);
+                self.notify_backend();
+            }
+            _ => (),
+        }
+    }
     pub fn get_file(&mut self) -> (Option<TTFile>, FileState)
     {
         (
@@ -673,6 +1092,15 @@ impl TTStateGUI
                     (Some(path), FileState::Ready) =>
                     {
                         ui.label(path.path());
+                        if let Some(error) = self.file.info.read().store_error.as_ref()
+                        {
+                            ui.label(
+                                RichText::new(" ttcf not saved!")
+                                    .color(Color32::RED)
+                                    .strong(),
+                            )
+                            .on_hover_text(error);
+                        }
                     }
                     (Some(path), FileState::Loaded) =>
                     {
@@ -682,8 +1110,11 @@ impl TTStateGUI
                         //enable views generation
                         self.views.iter_mut().for_each(|view| {
                             view.state.store(TTViewState::Changed, Ordering::Relaxed);
-                            *view.view_mode.position.write() = [0, 0, 0, 0, 0].into_dimension();
+                            *view.view_mode.position.write() = [0, 0, 0, 0, 0, 0].into_dimension();
                         });
+                        self.plot
+                            .state
+                            .store(TTViewState::Changed, Ordering::Relaxed);
                         self.notify_backend();
                         ui.label(path.path());
                         ui.label(" Processing...");
@@ -695,7 +1126,7 @@ impl TTStateGUI
                         ui.label(" Processing Wavelet transforms...");
                         ui.spinner();
                     }
-                    (Some(path), FileState::ProcessingFourier) =>
+                    (Some(path), FileState::ProcessingFourier | FileState::Reprocessing) =>
                     {
                         ui.label(path.path());
                         ui.label(" Processing Fourier transforms...");
@@ -704,20 +1135,28 @@ impl TTStateGUI
                     (Some(path), FileState::Error) =>
                     {
                         ui.label(path.path());
-                        ui.label(
-                            RichText::new(" !!! Invalid file !!!")
-                                .color(Color32::RED)
-                                .strong(),
-                        );
+                        let text = match self.file.info.read().load_error.as_ref()
+                        {
+                            Some(error) => format!(" !!! Invalid file: {} !!!", error),
+                            None => " !!! Invalid file !!!".to_string(),
+                        };
+                        ui.label(RichText::new(text).color(Color32::RED).strong());
                         ui.spinner();
                     }
                     (Some(path), _) =>
                     {
                         ui.label(path.path());
                         ui.label(" Loading...");
-                        ui.spinner();
+                        let progress = self.file.info.read().progress.get();
+                        ui.add(
+                            ProgressBar::new(progress)
+                                .desired_width(150.0)
+                                .show_percentage()
+                                .animate(true),
+                        );
                     }
                 }
+                self.file.show_metadata(ui);
                 if ui.button("…").clicked()
                 {
                     if let Some(path) = TTFile::new_from_file_dialog()
@@ -725,39 +1164,192 @@ impl TTStateGUI
                         self.set_file_path(Some(path));
                     }
                 }
+                if ui
+                    .button("📂")
+                    .on_hover_text("open directory of numbered png/tiff frames")
+                    .clicked()
+                {
+                    if let Some(path) = TTFile::new_from_folder_dialog()
+                    {
+                        self.set_file_path(Some(path));
+                    }
+                }
+                ui.label("| ttcf precision:").on_hover_text(
+                    "precision of ttcf file written after processing of other file types",
+                );
+                atomicCombobox!(self.settings.ttcf_precision, ui);
+                ui.label("| source:")
+                    .on_hover_text("source file handling after successful conversion to ttcf");
+                atomicCombobox!(self.settings.source_policy, ui);
+                if self.settings.source_policy.load(Relaxed) == SourcePolicy::Archive
+                {
+                    let archive_dir = self.settings.archive_dir.read().clone();
+                    if ui
+                        .button("📁")
+                        .on_hover_text(archive_dir.to_string_lossy())
+                        .clicked()
+                    {
+                        if let Some(dir) = FileDialog::new().pick_folder()
+                        {
+                            *self.settings.archive_dir.write() = dir;
+                        }
+                    }
+                }
                 if self.settings.roi_zoom.show_switchable(ui, "zoom ROI")
                 {
                     changed = true;
                     self.settings.changed(true);
                 }
                 changed |= atomicCombobox!(self.settings.select_mode, ui);
+                self.show_plots.show_switchable(ui, "plots");
+                /*differential view/pre-processing reference frame*/
+                {
+                    let mut ranged_val = RangedVal {
+                        val : self.settings.reference_frame.load(Relaxed),
+                        min : 0,
+                        max : self.settings.full_size.read()[TTAxis::T as usize],
+                    };
+                    ui.label("| ref. frame:");
+                    if ranged_val.show(ui)
+                    {
+                        self.settings.reference_frame.store(ranged_val.val, Relaxed);
+                        changed = true;
+                        self.settings.changed(true);
+                        if self.settings.diff_preprocess.load(Relaxed)
+                        {
+                            self.reprocess();
+                        }
+                    }
+                }
+                if self
+                    .settings
+                    .diff_preprocess
+                    .show_switchable(ui, "diff. pre-processing")
+                {
+                    self.reprocess();
+                }
+                ui.label("| frame rate [Hz]:");
+                if ui
+                    .add(
+                        DragValue::new(&mut *self.settings.frame_rate.write())
+                            .speed(0.1)
+                            .clamp_range(0.001..=f64::MAX),
+                    )
+                    .changed()
+                {
+                    changed = true;
+                    self.settings.changed(true);
+                }
+                ui.label("| scale as:");
+                if atomicCombobox!(self.settings.scale_unit, ui)
+                {
+                    changed = true;
+                }
+                ui.label("| window:");
+                if atomicCombobox!(self.settings.window, ui)
+                {
+                    self.reprocess();
+                }
+                /*range shared by views with linked colors follows their values*/
+                {
+                    if let Some(range) = self
+                        .views
+                        .iter_mut()
+                        .filter(|view| view.view_mode.scale_mode.load(Relaxed) == ScaleMode::Linked)
+                        .filter_map(|view| view.image.read().range)
+                        .reduce(|a, b| [a[0].min(b[0]), a[1].max(b[1])])
+                    {
+                        if range != *self.settings.linked_range.read()
+                        {
+                            //raw values do not depend on color scale, so single refresh is enough
+                            *self.settings.linked_range.write() = range;
+                            self.views
+                                .iter()
+                                .filter(|view| {
+                                    view.view_mode.scale_mode.load(Relaxed) == ScaleMode::Linked
+                                })
+                                .for_each(|view| view.state.store(TTViewState::Changed, Relaxed));
+                            changed = true;
+                        }
+                        ui.label(format!(
+                            "| linked colors: {:.4} - {:.4}",
+                            range[0], range[1]
+                        ));
+                    }
+                }
+                ui.label("| custom colors:");
+                if ui
+                    .button("📁")
+                    .on_hover_text(
+                        "load gradient of Custom color map: text file with one color (eg. #ff8000 \
+                         or 255 128 0) per line",
+                    )
+                    .clicked()
+                {
+                    if let Some(path) = FileDialog::new().pick_file()
+                    {
+                        self.gradient_error =
+                            TTGradients::load_custom(&path).err().map(|e| e.to_string());
+                        self.views
+                            .iter()
+                            .filter(|view| {
+                                view.view_mode.color_map.load(Relaxed) == ColorMap::Custom
+                            })
+                            .for_each(|view| view.state.store(TTViewState::Changed, Relaxed));
+                        changed = true;
+                    }
+                }
+                if let Some(err) = &self.gradient_error
+                {
+                    ui.label(RichText::new("!").color(Color32::RED).strong())
+                        .on_hover_text(err);
+                }
             });
-            let available_height = ui.available_height() / 2.0;
+            let source = self
+                .get_file()
+                .0
+                .map(|file| file.path())
+                .unwrap_or_default();
+            let plots_height = if self.show_plots
+            {
+                ui.available_height() / 3.0
+            }
+            else
+            {
+                0.0
+            };
+            let available_height = (ui.available_height() - plots_height) / 2.0;
             let available_width = ui.available_width() / 2.0;
             TableBuilder::new(ui)
+                .vscroll(false)
                 .column(Column::exact(available_width))
                 .column(Column::exact(available_width))
                 .body(|mut body| {
                     body.row(available_height, |mut row| {
                         row.col(|ui| {
-                            changed |= self.views[0].show(ui);
+                            changed |= self.views[0].show(ui, &source);
                         });
                         row.col(|ui| {
-                            changed |= self.views[1].show(ui);
+                            changed |= self.views[1].show(ui, &source);
                         });
                     });
                     body.row(available_height, |mut row| {
                         row.col(|ui| {
-                            changed |= self.views[2].show(ui);
+                            changed |= self.views[2].show(ui, &source);
                         });
                         row.col(|ui| {
-                            changed |= self.views[3].show(ui);
+                            changed |= self.views[3].show(ui, &source);
                         });
                     });
                 });
+            if self.show_plots
+            {
+                changed |= self.plot.show(ui);
+            }
             if changed
             {
                 ui.ctx().request_repaint(); //speed up next screen refresh
+                self.plot.changed();
                 if self.settings.changed(false)
                 {
                     //if roi has changed refresh all views
@@ -776,23 +1368,10 @@ impl Drop for TTStateGUI
     {
         self.stop_flag.store(true, Ordering::SeqCst);
         self.notify_backend();
-        loop
+        //ongoing processing checks stop flag, so backend finishes promptly
+        if let Some(handle) = self.backend_handle.take()
         {
-            if let Some(handle) = &self.backend_handle
-            {
-                if handle.is_finished()
-                {
-                    break;
-                }
-                else
-                {
-                    continue;
-                }
-            }
-            else
-            {
-                break;
-            }
+            let _ = handle.join();
         }
     }
 }
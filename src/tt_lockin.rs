@@ -0,0 +1,201 @@
+use ndarray::{s, Array1, Array2, ArrayView1, Zip};
+use num_complex::Complex64;
+use std::f64::consts::PI;
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::tt_common::*;
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl TTInputData
+{
+    ///lock-in demodulation (X-Y) at `params.lockin_freq`;
+    ///complex amplitude (A*e^(i*phi) for signal A*cos(w*t+phi)) is converted according to `params.display_mode`
+    pub fn lock_in(&self, params : &ViewMode, settings : &GlobalSettings) -> Array2<f64>
+    {
+        //excitation period in frames
+        let period = *settings.frame_rate.read() / *params.lockin_freq.read();
+        let periods = (self.frames as f64 / period).floor().max(1.0);
+        //integer number of periods (whole sequence if it is shorter than one period)
+        let len = ((periods * period).round() as usize).clamp(1, self.frames);
+        let data = self.data.slice(s![.., .., ..len]);
+        let lock_in = match params.lockin_method.load(Relaxed)
+        {
+            LockInMethod::Correlation =>
+            {
+                let omega = 2.0 * PI / period;
+                let cos : Array1<f64> = (0..len).map(|t| (omega * t as f64).cos()).collect();
+                let sin : Array1<f64> = (0..len).map(|t| (omega * t as f64).sin()).collect();
+                let (cos_sum, sin_sum) = (cos.sum(), sin.sum());
+                let norm = 2.0 / len as f64;
+                Zip::from(data.lanes(AXIS_T)).par_map_collect(|lane| {
+                    //remove offset (mean), so it does not leak into result for non integer period
+                    let mean = lane.mean().unwrap_or(0.0);
+                    Complex64::new(
+                        norm * (lane.dot(&cos) - mean * cos_sum),
+                        -norm * (lane.dot(&sin) - mean * sin_sum),
+                    )
+                })
+            }
+            LockInMethod::Four_Point =>
+            {
+                let periods = periods as usize;
+                let sample = |lane : &ArrayView1<'_, f64>, pos : f64| {
+                    //linear interpolation between frames
+                    let idx = pos.floor() as usize;
+                    let frac = pos - idx as f64;
+                    if idx + 1 < len
+                    {
+                        lane[idx] * (1.0 - frac) + lane[idx + 1] * frac
+                    }
+                    else
+                    {
+                        lane[len - 1]
+                    }
+                };
+                Zip::from(data.lanes(AXIS_T)).par_map_collect(|lane| {
+                    let sum = (0..periods).fold(Complex64::new(0.0, 0.0), |sum, p| {
+                        let s : [f64; 4] = [0.0, 1.0, 2.0, 3.0]
+                            .map(|k| sample(&lane, (p as f64 + 0.25 * k) * period));
+                        sum + Complex64::new(s[0] - s[2], s[3] - s[1])
+                    });
+                    sum / (2 * periods) as f64
+                })
+            }
+        };
+        let lock_in = if settings.roi_zoom.load(Relaxed)
+        {
+            let roi_h = settings.get_roi(TTAxis::X);
+            let roi_v = settings.get_roi(TTAxis::Y);
+            lock_in.slice_move(s![roi_h, roi_v])
+        }
+        else
+        {
+            lock_in
+        };
+        //convert to requested format
+        match params.display_mode.load(Relaxed)
+        {
+            ComplexResultMode::Phase => lock_in.map(|x| x.arg()), //radians
+            ComplexResultMode::Magnitude => lock_in.map(|x| x.norm()),
+            ComplexResultMode::Real => lock_in.map(|x| x.re),
+            ComplexResultMode::Imaginary => lock_in.map(|x| x.im),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use ndarray::Array3;
+
+    const WIDTH : usize = 3;
+    const HEIGHT : usize = 2;
+    const FRAME_RATE : f64 = 40.0;
+    const OFFSET : f64 = 100.0;
+
+    ///amplitude & phase of pixel (x, y)
+    fn amplitude(x : usize, y : usize) -> f64 { 1.0 + x as f64 + 0.5 * y as f64 }
+    fn phase(x : usize, y : usize) -> f64 { 0.4 * x as f64 - 1.1 * y as f64 + 0.3 }
+
+    ///A*cos(2*pi*f*t + phi) + offset, zero padded after `frames`
+    fn signal(
+        frames : usize,
+        padded : usize,
+        freq : f64,
+        amplitude : fn(usize, usize) -> f64,
+    ) -> TTInputData
+    {
+        let data = Array3::from_shape_fn((WIDTH, HEIGHT, padded), |(x, y, t)| {
+            if t < frames
+            {
+                let time = t as f64 / FRAME_RATE;
+                amplitude(x, y) * (2.0 * PI * freq * time + phase(x, y)).cos() + OFFSET
+            }
+            else
+            {
+                0.0
+            }
+        });
+        TTInputData {
+            frames,
+            width : WIDTH,
+            height : HEIGHT,
+            data,
+            metadata : Default::default(),
+        }
+    }
+    fn lock_in(
+        input : &TTInputData,
+        freq : f64,
+        method : LockInMethod,
+        mode : ComplexResultMode,
+    ) -> Array2<f64>
+    {
+        let view_mode = ViewMode::new(ViewModeDomain::LockInView, Default::default(), mode);
+        *view_mode.lockin_freq.write() = freq;
+        view_mode.lockin_method.store(method, Relaxed);
+        let settings = GlobalSettings::default();
+        *settings.frame_rate.write() = FRAME_RATE;
+        input.lock_in(&view_mode, &settings)
+    }
+
+    #[test]
+    fn amplitude_and_phase_are_recovered()
+    {
+        //period of 8 frames, 8 whole periods
+        let (freq, frames) = (5.0, 64);
+        let input = signal(frames, frames, freq, amplitude);
+        for method in [LockInMethod::Correlation, LockInMethod::Four_Point]
+        {
+            let magnitude = lock_in(&input, freq, method, ComplexResultMode::Magnitude);
+            let phases = lock_in(&input, freq, method, ComplexResultMode::Phase);
+            assert_eq!(magnitude.dim(), (WIDTH, HEIGHT));
+            for ((x, y), &value) in magnitude.indexed_iter()
+            {
+                assert!(
+                    (value - amplitude(x, y)).abs() < 1e-9,
+                    "{}",
+                    method.as_ref()
+                );
+                assert!(
+                    (phases[[x, y]] - phase(x, y)).abs() < 1e-9,
+                    "{}",
+                    method.as_ref()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn period_longer_than_sequence()
+    {
+        //period of 16 frames, but only 10 frames (padded to 12)
+        let (freq, frames) = (2.5, 10);
+        let input = signal(frames, 12, freq, amplitude);
+        for method in [LockInMethod::Correlation, LockInMethod::Four_Point]
+        {
+            let magnitude = lock_in(&input, freq, method, ComplexResultMode::Magnitude);
+            assert_eq!(magnitude.dim(), (WIDTH, HEIGHT));
+            assert!(
+                magnitude.iter().all(|x| x.is_finite()),
+                "{}",
+                method.as_ref()
+            );
+        }
+        //whole sequence is used, so constant signal (padding excluded) has no component
+        let constant = signal(frames, 12, freq, |_, _| 0.0);
+        for method in [LockInMethod::Correlation, LockInMethod::Four_Point]
+        {
+            let magnitude = lock_in(&constant, freq, method, ComplexResultMode::Magnitude);
+            assert!(
+                magnitude.iter().all(|x| x.abs() < 1e-9),
+                "{}",
+                method.as_ref()
+            );
+        }
+    }
+}
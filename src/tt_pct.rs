@@ -0,0 +1,367 @@
+use ndarray::linalg::general_mat_vec_mul;
+use ndarray::{s, Array1, Array2, Array3, ArrayView3, Axis, Zip};
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::tt_common::*;
+
+//=======================================
+//================Consts=================
+//=======================================
+
+///max. number of calculated principal components
+pub const PCT_COMPONENTS : usize = 16;
+///additional vectors in randomized subspace (improves accuracy of last components)
+const OVERSAMPLING : usize = 8;
+const POWER_ITERATIONS : usize = 4;
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///Principal Component Thermography- truncated SVD of mean-centred sequence
+///(time as observations, pixels as variables)
+pub struct TTPct
+{
+    ///empirical orthogonal functions (X, Y, C); unit norm images
+    eofs :            Array3<f64>,
+    ///temporal principal components (T, C); scaled with singular values
+    pcs :             Array2<f64>,
+    singular_values : Array1<f64>,
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl TTPct
+{
+    ///number of components calculated for `input`
+    pub fn components(input : &TTInputData) -> usize
+    {
+        PCT_COMPONENTS
+            .min(input.frames)
+            .min(input.width * input.height)
+    }
+
+    pub fn new(input : &TTInputData) -> TTPct
+    {
+        let components = Self::components(input);
+        let data = input.data.slice(s![.., .., ..input.frames]);
+        let means = data.map_axis(AXIS_T, |lane| lane.mean().unwrap_or(0.0));
+        //randomized subspace iteration; `q` converges to dominant temporal subspace
+        //(rank of data is at most min(frames, pixels); `q` has to be full rank)
+        let rank = (components + OVERSAMPLING)
+            .min(input.frames)
+            .min(input.width * input.height);
+        let mut q = orthonormalize(random_matrix(input.frames, rank));
+        for _ in 0..POWER_ITERATIONS
+        {
+            let z = project(data, &means, &q);
+            q = orthonormalize(back_project(data, &means, &z));
+        }
+        //SVD of projected data (pixels x rank) through eigen decomposition of its Gram matrix
+        let z = project(data, &means, &q);
+        let z = z.into_shape((input.width * input.height, rank)).unwrap(); //owned array is in standard layout
+        let (eigenvalues, eigenvectors) = symmetric_eigen(z.t().dot(&z));
+        let mut order : Vec<usize> = (0..rank).collect();
+        order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+        let order = &order[..components];
+
+        let singular_values =
+            Array1::from_iter(order.iter().map(|&i| eigenvalues[i].max(0.0).sqrt()));
+        let mut w = Array2::zeros((rank, components));
+        order
+            .iter()
+            .enumerate()
+            .for_each(|(c, &i)| w.column_mut(c).assign(&eigenvectors.column(i)));
+        let mut eofs = z.dot(&w);
+        let mut pcs = q.dot(&w);
+        Zip::from(eofs.columns_mut())
+            .and(pcs.columns_mut())
+            .and(&singular_values)
+            .for_each(|mut eof, mut pc, &sv| {
+                if sv > 0.0
+                {
+                    eof /= sv;
+                }
+                pc *= sv;
+                //sign of SVD components is arbitrary; make largest PC value positive
+                let max = pc.iter().fold(0.0_f64, |max, &x| {
+                    if x.abs() > max.abs()
+                    {
+                        x
+                    }
+                    else
+                    {
+                        max
+                    }
+                });
+                if max < 0.0
+                {
+                    eof.map_inplace(|x| *x = -*x);
+                    pc.map_inplace(|x| *x = -*x);
+                }
+            });
+        TTPct {
+            eofs : eofs
+                .into_shape((input.width, input.height, components))
+                .unwrap(), //result of `dot` is in standard layout
+            pcs,
+            singular_values,
+        }
+    }
+
+    pub fn singular_values(&self) -> &Array1<f64> { &self.singular_values }
+
+    ///views: EOF images (X-Y per component, X-c, c-Y) & temporal principal components (t-c)
+    pub fn snapshot(&self, params : &ViewMode, settings : &GlobalSettings) -> Array2<f64>
+    {
+        let mut view_axes = params.get_view_axes();
+        //views are calculated with axes in `TTAxis` order
+        view_axes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let position = params.position.read();
+        let view = match view_axes
+        {
+            [TTAxis::T, TTAxis::C] => self.pcs.view(),
+            _ =>
+            {
+                let settings_axis = params.get_settings_axes()[0];
+                let axis = if settings_axis == TTAxis::C
+                {
+                    Axis(2)
+                }
+                else
+                {
+                    Axis(settings_axis as usize)
+                };
+                self.eofs.index_axis(axis, position[settings_axis as usize])
+            }
+        };
+        if settings.roi_zoom.load(Relaxed)
+        {
+            let roi_h = settings.get_roi(view_axes[0]);
+            let roi_v = settings.get_roi(view_axes[1]);
+            view.slice(s![roi_h, roi_v]).to_owned()
+        }
+        else
+        {
+            view.to_owned()
+        }
+    }
+}
+
+//=======================================
+//================Helpers================
+//=======================================
+
+///mean-centred data (X, Y, T) projected on columns of `q` (T x rank) -> (X, Y, rank)
+fn project(data : ArrayView3<'_, f64>, means : &Array2<f64>, q : &Array2<f64>) -> Array3<f64>
+{
+    let (width, height, _) = data.dim();
+    let q_sum = q.sum_axis(Axis(0));
+    let mut z = Array3::zeros((width, height, q.ncols()));
+    Zip::from(z.lanes_mut(Axis(2)))
+        .and(data.lanes(AXIS_T))
+        .and(means)
+        .par_for_each(|mut z, lane, &mean| {
+            general_mat_vec_mul(1.0, &q.t(), &lane, 0.0, &mut z);
+            z.scaled_add(-mean, &q_sum);
+        });
+    z
+}
+
+///transposed mean-centred data (T, X*Y) multiplied by `z` (X, Y, rank) -> (T, rank)
+fn back_project(data : ArrayView3<'_, f64>, means : &Array2<f64>, z : &Array3<f64>) -> Array2<f64>
+{
+    let frames = data.len_of(AXIS_T);
+    let rank = z.len_of(Axis(2));
+    Zip::from(data.lanes(AXIS_T))
+        .and(z.lanes(Axis(2)))
+        .and(means)
+        .par_fold(
+            || Array2::zeros((frames, rank)),
+            |mut acc, lane, z, &mean| {
+                Zip::from(acc.rows_mut())
+                    .and(&lane)
+                    .for_each(|mut row, &x| row.scaled_add(x - mean, &z));
+                acc
+            },
+            |a, b| a + b,
+        )
+}
+
+///modified Gram-Schmidt orthonormalization of columns
+fn orthonormalize(mut q : Array2<f64>) -> Array2<f64>
+{
+    for j in 0..q.ncols()
+    {
+        for i in 0..j
+        {
+            let (done, mut col) = q.multi_slice_mut((s![.., i], s![.., j]));
+            let r = done.dot(&col);
+            col.scaled_add(-r, &done);
+        }
+        let mut col = q.column_mut(j);
+        let norm = col.dot(&col).sqrt();
+        if norm > 0.0
+        {
+            col /= norm;
+        }
+    }
+    q
+}
+
+///deterministic pseudo random (xorshift) matrix with values in [-1; 1)
+fn random_matrix(rows : usize, cols : usize) -> Array2<f64>
+{
+    let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+    Array2::from_shape_simple_fn((rows, cols), || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1_u64 << 52) as f64 - 1.0
+    })
+}
+
+///cyclic Jacobi eigenvalue algorithm for small symmetric matrices; returns (eigenvalues, eigenvectors as columns)
+fn symmetric_eigen(mut a : Array2<f64>) -> (Array1<f64>, Array2<f64>)
+{
+    let n = a.nrows();
+    let mut v = Array2::eye(n);
+    for _sweep in 0..64
+    {
+        let diag = a.diag().fold(0.0, |sum, x| sum + x * x);
+        let off_diag = a.fold(0.0, |sum, x| sum + x * x) - diag;
+        if off_diag <= f64::EPSILON * f64::EPSILON * diag
+        {
+            break;
+        }
+        for p in 0..n
+        {
+            for q in p + 1..n
+            {
+                if a[[p, q]] == 0.0
+                {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + theta.hypot(1.0));
+                let c = 1.0 / t.hypot(1.0);
+                let s = t * c;
+                let rotate = |m : &mut Array2<f64>, k : [usize; 2], l : [usize; 2]| {
+                    let (mk, ml) = (m[k], m[l]);
+                    m[k] = c * mk - s * ml;
+                    m[l] = s * mk + c * ml;
+                };
+                for k in 0..n
+                {
+                    rotate(&mut a, [k, p], [k, q]);
+                }
+                for k in 0..n
+                {
+                    rotate(&mut a, [p, k], [q, k]);
+                }
+                for k in 0..n
+                {
+                    rotate(&mut v, [k, p], [k, q]);
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::f64::consts::PI;
+
+    const WIDTH : usize = 6;
+    const HEIGHT : usize = 5;
+    const FRAMES : usize = 32;
+
+    fn harmonic(k : f64, t : usize) -> f64 { 2.0 * PI * k * t as f64 / FRAMES as f64 }
+    ///largest |value| is at t=0 and positive
+    fn pc1(t : usize) -> f64 { 3.0 * harmonic(1.0, t).cos() + harmonic(4.0, t).cos() }
+    ///largest |value| is negative (at t=27), so recovered component is flipped
+    fn pc2(t : usize) -> f64
+    {
+        2.0 * harmonic(2.0, t).sin() + harmonic(3.0, t).cos() + 0.5 * harmonic(2.0, t).cos()
+    }
+    ///unit norm images, orthogonal to each other
+    fn eof1(_x : usize) -> f64 { 1.0 / ((WIDTH * HEIGHT) as f64).sqrt() }
+    fn eof2(x : usize) -> f64
+    {
+        let sign = if x < WIDTH / 2 { 1.0 } else { -1.0 };
+        sign / ((WIDTH * HEIGHT) as f64).sqrt()
+    }
+
+    #[test]
+    fn orthonormalize_gives_orthonormal_columns()
+    {
+        let q = orthonormalize(random_matrix(10, 4));
+        for ((i, j), &x) in q.t().dot(&q).indexed_iter()
+        {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            assert!((x - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn symmetric_eigen_diagonalizes()
+    {
+        let a = ndarray::arr2(&[[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 5.0]]);
+        let (values, vectors) = symmetric_eigen(a.clone());
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        for (value, expected) in sorted.iter().zip([1.0, 3.0, 5.0])
+        {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        //A*v = lambda*v for each column
+        let residual = a.dot(&vectors) - &vectors * &values;
+        assert!(residual.iter().all(|x| x.abs() < 1e-12));
+    }
+
+    #[test]
+    fn recovers_known_components()
+    {
+        //two components + offset (removed by mean centring) + small full rank noise
+        let noise = random_matrix(WIDTH * HEIGHT, FRAMES)
+            .into_shape((WIDTH, HEIGHT, FRAMES))
+            .unwrap();
+        let data = Array3::from_shape_fn((WIDTH, HEIGHT, FRAMES), |(x, y, t)| {
+            eof1(x) * pc1(t) + eof2(x) * pc2(t) + 20.0 + 1e-4 * noise[[x, y, t]]
+        });
+        let input = TTInputData {
+            frames : FRAMES,
+            width : WIDTH,
+            height : HEIGHT,
+            data,
+            metadata : Default::default(),
+        };
+        let pct = TTPct::new(&input);
+
+        let norm = |pc : fn(usize) -> f64| (0..FRAMES).map(|t| pc(t) * pc(t)).sum::<f64>().sqrt();
+        let sv = pct.singular_values();
+        assert_eq!(sv.len(), PCT_COMPONENTS);
+        assert!((sv[0] - norm(pc1)).abs() < 1e-2);
+        assert!((sv[1] - norm(pc2)).abs() < 1e-2);
+        assert!(sv[2] < 1e-2);
+        for t in 0..FRAMES
+        {
+            assert!((pct.pcs[[t, 0]] - pc1(t)).abs() < 1e-2);
+            assert!((pct.pcs[[t, 1]] + pc2(t)).abs() < 1e-2);
+        }
+        for ((x, _y, c), &value) in pct.eofs.indexed_iter()
+        {
+            match c
+            {
+                0 => assert!((value - eof1(x)).abs() < 1e-3),
+                1 => assert!((value + eof2(x)).abs() < 1e-3),
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use ndarray::s;
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::cwt::TTLazyCWT;
+use crate::tt_common::*;
+use crate::tt_fourier::TTFourier;
+use crate::wavelet::{WaveletBank, WaveletType};
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///1D curves at single pixel (`GlobalSettings::crossection`) as [x, y] points;
+///curves of transforms that are not calculated yet are empty
+#[derive(Clone, Default)]
+pub struct TTPixelPlots
+{
+    ///X, Y & scale index of plotted curves
+    pub position :          [usize; 3],
+    ///temperature vs time [s]
+    pub temperature :       Vec<[f64; 2]>,
+    ///Fourier magnitude vs frequency [Hz]
+    pub fourier_magnitude : Vec<[f64; 2]>,
+    ///Fourier phase [rad] vs frequency [Hz]
+    pub fourier_phase :     Vec<[f64; 2]>,
+    ///wavelet magnitude vs time [s]
+    pub wavelet_magnitude : Vec<[f64; 2]>,
+    ///wavelet phase [rad] vs time [s]
+    pub wavelet_phase :     Vec<[f64; 2]>,
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl TTPixelPlots
+{
+    ///returns `None` if processing was cancelled
+    pub fn new(
+        input : &TTInputData,
+        fourier : Option<&TTFourier>,
+        lazy_cwt : Option<&TTLazyCWT>,
+        wavelet_bank : &mut WaveletBank,
+        wavelet : WaveletType,
+        settings : &GlobalSettings,
+        cancel : &TTCancelToken,
+    ) -> Option<TTPixelPlots>
+    {
+        let mut position = *settings.crossection.read();
+        let full_size = *settings.full_size.read();
+        for axis in [TTAxis::X, TTAxis::Y, TTAxis::S]
+        {
+            position[axis as usize] = position[axis as usize].min(full_size[axis as usize]);
+        }
+        let [x, y, s] = [TTAxis::X, TTAxis::Y, TTAxis::S].map(|axis| position[axis as usize]);
+        let frame_rate = *settings.frame_rate.read();
+        let mut plots = TTPixelPlots {
+            position : [x, y, s],
+            temperature : time_curve(
+                input.data.slice(s![x, y, ..input.frames]).iter(),
+                frame_rate,
+            ),
+            ..Default::default()
+        };
+        if let Some(fourier) = fourier
+        {
+            let spectrum = fourier.data().slice(s![x, y, ..]);
+            let df = frame_rate / input.data.dim().2 as f64;
+            plots.fourier_magnitude = spectrum
+                .iter()
+                .enumerate()
+                .map(|(f, c)| [f as f64 * df, c.norm()])
+                .collect();
+            plots.fourier_phase = spectrum
+                .iter()
+                .enumerate()
+                .map(|(f, c)| [f as f64 * df, c.arg()])
+                .collect();
+        }
+        if let Some(lazy_cwt) = lazy_cwt
+        {
+            //wavelet transform is calculated as X-t view zoomed to single column
+            let params = ViewMode::new(
+                ViewModeDomain::FastWaveletView,
+                wavelet,
+                ComplexResultMode::Magnitude,
+            );
+            let mode_counter = ViewMode::ViewModeAxes[ViewModeDomain::FastWaveletView as usize]
+                .iter()
+                .position(|(axes, _)| *axes == [TTAxis::X, TTAxis::T])
+                .unwrap(); //X-t is one of wavelet view modes
+            params.mode_counter.store(mode_counter, Relaxed);
+            *params.position.write() = position;
+            let column = settings.clone();
+            column.roi_zoom.store(true, Relaxed);
+            column.roi_min.write()[TTAxis::X as usize] = x;
+            column.roi_max.write()[TTAxis::X as usize] = x + 1;
+            column.roi_min.write()[TTAxis::T as usize] = 0;
+            column.roi_max.write()[TTAxis::T as usize] = input.frames;
+
+            let magnitude = lazy_cwt.cwt(wavelet_bank, &params, &column, cancel)?;
+            params.display_mode.store(ComplexResultMode::Phase, Relaxed);
+            let phase = lazy_cwt.cwt(wavelet_bank, &params, &column, cancel)?;
+            plots.wavelet_magnitude = time_curve(magnitude.iter(), frame_rate);
+            plots.wavelet_phase = time_curve(phase.iter(), frame_rate);
+        }
+        Some(plots)
+    }
+}
+
+//=======================================
+//================Helpers================
+//=======================================
+
+fn time_curve<'a>(values : impl Iterator<Item = &'a f64>, frame_rate : f64) -> Vec<[f64; 2]>
+{
+    values
+        .enumerate()
+        .map(|(t, &v)| [t as f64 / frame_rate, v])
+        .collect()
+}
@@ -0,0 +1,303 @@
+use ndarray::linalg::general_mat_vec_mul;
+use ndarray::{s, Array1, Array2, Array3, Axis, Zip};
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::tt_common::*;
+
+//=======================================
+//================Consts=================
+//=======================================
+
+///degree of polynomial fitted to ln(T)
+pub const TSR_DEFAULT_DEGREE : usize = 5;
+pub const TSR_MAX_DEGREE : usize = 9;
+
+//=======================================
+//=================Types=================
+//=======================================
+
+///Thermographic Signal Reconstruction- per pixel polynomial fit of ln(T) vs ln(t)
+pub struct TTTsr
+{
+    degree :         usize,
+    diff_reference : Option<usize>,
+    ///polynomial coefficients (X, Y, degree+1) in normalized log time
+    coeffs :         Array3<f64>,
+    ///polynomial basis (T, degree+1) for reconstruction, 1st & 2nd logarithmic derivative (indexed with `TSRMode`)
+    basis :          [Array2<f64>; 3],
+}
+
+//=======================================
+//============Implementations============
+//=======================================
+
+impl TTTsr
+{
+    ///with differential pre-processing (`params.diff_reference`) temperature rise after reference frame is fitted
+    ///(t=1 at first frame after reference), otherwise whole sequence (t=1 at first frame);
+    ///non-positive temperatures are clamped before taking logarithm
+    pub fn new(input : &TTInputData, params : &TTProcessingParams, degree : usize) -> TTTsr
+    {
+        let (first, origin) = match params.diff_reference
+        {
+            Some(reference) => ((reference + 1).min(input.frames - 1), reference as f64),
+            None => (0, -1.0),
+        };
+        let fitted = input.frames - first;
+        let fit_degree = degree.min(fitted - 1);
+        let log_time = |frame : usize| (frame as f64 - origin).max(1.0).ln();
+        //normalize log time to [-1; 1] for better conditioning
+        let (min, max) = (log_time(first), log_time(input.frames - 1));
+        let mean = 0.5 * (max + min);
+        let scale = if max > min { 0.5 * (max - min) } else { 1.0 };
+        let u = |frame : usize| (log_time(frame) - mean) / scale;
+
+        //least squares: coeffs = (V^T V)^-1 V^T ln(T), V- Vandermonde matrix of fitted frames
+        let vandermonde = Array2::from_shape_fn((fitted, fit_degree + 1), |(f, k)| {
+            u(first + f).powi(k as i32)
+        });
+        let pseudo_inverse = invert(vandermonde.t().dot(&vandermonde)).dot(&vandermonde.t());
+        let mut coeffs = Array3::zeros((input.width, input.height, degree + 1));
+        Zip::from(coeffs.lanes_mut(Axis(2)))
+            .and(input.data.lanes(AXIS_T))
+            .par_for_each(|mut coeffs, lane| {
+                let reference = params.diff_reference.map_or(0.0, |r| lane[r]);
+                let log_temp = lane
+                    .slice(s![first..input.frames])
+                    .map(|x| (x - reference).max(f64::EPSILON).ln());
+                general_mat_vec_mul(
+                    1.0,
+                    &pseudo_inverse,
+                    &log_temp,
+                    0.0,
+                    &mut coeffs.slice_mut(s![..=fit_degree]),
+                );
+            });
+
+        let basis = [0, 1, 2].map(|derivative : i32| {
+            Array2::from_shape_fn((input.frames, degree + 1), |(f, k)| {
+                let k = k as i32;
+                if k < derivative
+                {
+                    0.0
+                }
+                else
+                {
+                    //d^n/d(ln t)^n u^k
+                    let falling = (0..derivative).fold(1.0, |p, i| p * (k - i) as f64);
+                    falling * u(f).powi(k - derivative) / scale.powi(derivative)
+                }
+            })
+        });
+        TTTsr {
+            degree,
+            diff_reference : params.diff_reference,
+            coeffs,
+            basis,
+        }
+    }
+
+    ///`false` if fit has to be recalculated for requested params
+    pub fn matches(&self, params : &TTProcessingParams, degree : usize) -> bool
+    {
+        self.degree == degree && self.diff_reference == params.diff_reference
+    }
+
+    ///polynomial coefficients (X, Y, degree+1) in log time normalized to [-1; 1]
+    pub fn coeffs(&self) -> &Array3<f64> { &self.coeffs }
+
+    pub fn snapshot(&self, params : &ViewMode, settings : &GlobalSettings) -> Array2<f64>
+    {
+        let mode = params.tsr_mode.load(Relaxed);
+        let basis = &self.basis[mode as usize];
+        let settings_axis = params.get_settings_axes()[0];
+        let position = params.position.read()[settings_axis as usize];
+        let mut view = match settings_axis
+        {
+            TTAxis::T =>
+            {
+                let (width, height, len) = self.coeffs.dim();
+                let coeffs = self
+                    .coeffs
+                    .view()
+                    .into_shape((width * height, len))
+                    .unwrap(); //owned array is in standard layout
+                let view : Array1<f64> = coeffs.dot(&basis.row(position));
+                view.into_shape((width, height)).unwrap()
+            }
+            //X-t (Y fixed) or t-Y (X fixed)
+            _ =>
+            {
+                self.coeffs
+                    .index_axis(Axis(settings_axis as usize), position)
+                    .dot(&basis.t())
+            }
+        };
+        if mode == TSRMode::Reconstruction
+        {
+            view.mapv_inplace(f64::exp);
+        }
+        if settings.roi_zoom.load(Relaxed)
+        {
+            let mut view_axes = params.get_view_axes();
+            //views are calculated with axes in `TTAxis` order
+            view_axes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let roi_h = settings.get_roi(view_axes[0]);
+            let roi_v = settings.get_roi(view_axes[1]);
+            view.slice_move(s![roi_h, roi_v])
+        }
+        else
+        {
+            view
+        }
+    }
+}
+
+//=======================================
+//================Helpers================
+//=======================================
+
+///Gauss-Jordan inversion (with partial pivoting) of small matrix
+fn invert(mut a : Array2<f64>) -> Array2<f64>
+{
+    let n = a.nrows();
+    let mut inv = Array2::eye(n);
+    for col in 0..n
+    {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[[i, col]].abs().total_cmp(&a[[j, col]].abs()))
+            .unwrap();
+        for k in 0..n
+        {
+            a.swap([col, k], [pivot, k]);
+            inv.swap([col, k], [pivot, k]);
+        }
+        let p = a[[col, col]];
+        if p == 0.0
+        {
+            //singular matrix
+            continue;
+        }
+        a.row_mut(col).mapv_inplace(|x| x / p);
+        inv.row_mut(col).mapv_inplace(|x| x / p);
+        for row in 0..n
+        {
+            if row != col
+            {
+                let factor = a[[row, col]];
+                let (a_col, a_row) = (a.row(col).to_owned(), a.row(row).to_owned());
+                a.row_mut(row).assign(&(a_row - factor * a_col));
+                let (inv_col, inv_row) = (inv.row(col).to_owned(), inv.row(row).to_owned());
+                inv.row_mut(row).assign(&(inv_row - factor * inv_col));
+            }
+        }
+    }
+    inv
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const WIDTH : usize = 3;
+    const HEIGHT : usize = 2;
+    const FRAMES : usize = 40;
+    const DEGREE : usize = 3;
+    ///ln(T) = A0 + A1*ln(t) + A2*ln(t)^2
+    const A : [f64; 3] = [0.5, -0.5, 0.1];
+
+    fn poly(log_t : f64) -> f64 { A[0] + A[1] * log_t + A[2] * log_t * log_t }
+
+    ///fits `poly` (exact for `DEGREE` >= 2) and checks coefficients and basis of all modes
+    fn check_exact_fit(diff_reference : Option<usize>)
+    {
+        //time of frame with t=1 at first fitted frame
+        let time = |f : usize| {
+            match diff_reference
+            {
+                Some(reference) => f as f64 - reference as f64,
+                None => f as f64 + 1.0,
+            }
+        };
+        let first = diff_reference.map_or(0, |reference| reference + 1);
+        let data = Array3::from_shape_fn((WIDTH, HEIGHT, FRAMES), |(x, y, f)| {
+            //per pixel offset is removed by differential pre-processing
+            let offset = diff_reference.map_or(0.0, |_| 300.0 + (x + WIDTH * y) as f64);
+            if f < first
+            {
+                offset
+            }
+            else
+            {
+                offset + poly(time(f).ln()).exp()
+            }
+        });
+        let input = TTInputData {
+            frames : FRAMES,
+            width : WIDTH,
+            height : HEIGHT,
+            data,
+            metadata : Default::default(),
+        };
+        let params = TTProcessingParams {
+            diff_reference,
+            ..Default::default()
+        };
+        let tsr = TTTsr::new(&input, &params, DEGREE);
+        assert!(tsr.matches(&params, DEGREE));
+
+        //expected coefficients after substitution ln(t) = mean + scale*u
+        let (min, max) = (time(first).ln(), time(FRAMES - 1).ln());
+        let (mean, scale) = (0.5 * (max + min), 0.5 * (max - min));
+        let expected = [
+            A[0] + A[1] * mean + A[2] * mean * mean,
+            (A[1] + 2.0 * A[2] * mean) * scale,
+            A[2] * scale * scale,
+            0.0,
+        ];
+        for lane in tsr.coeffs().lanes(Axis(2))
+        {
+            for (c, e) in lane.iter().zip(expected)
+            {
+                assert!((c - e).abs() < 1e-8, "coefficient {} != {}", c, e);
+            }
+            //reconstruction, 1st & 2nd derivative with respect to ln(t)
+            for f in first..FRAMES
+            {
+                let log_t = time(f).ln();
+                let derivatives = [poly(log_t), A[1] + 2.0 * A[2] * log_t, 2.0 * A[2]];
+                for (basis, expected) in tsr.basis.iter().zip(derivatives)
+                {
+                    let value = basis.row(f).dot(&lane);
+                    assert!(
+                        (value - expected).abs() < 1e-8,
+                        "frame {}: {} != {}",
+                        f,
+                        value,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fits_polynomial_in_log_time() { check_exact_fit(None); }
+
+    #[test]
+    fn fits_polynomial_with_diff_reference() { check_exact_fit(Some(4)); }
+
+    #[test]
+    fn invert_with_pivoting()
+    {
+        //zero on diagonal requires row swap
+        let a = ndarray::arr2(&[[0.0, 2.0, 1.0], [1.0, 1.0, 0.0], [3.0, 0.0, 1.0]]);
+        let product = a.dot(&invert(a.clone()));
+        for ((i, j), &x) in product.indexed_iter()
+        {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            assert!((x - expected).abs() < 1e-12);
+        }
+    }
+}
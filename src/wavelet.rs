@@ -286,6 +286,22 @@ pub enum WaveletType
     Poisson_2,
 }
 use WaveletType::*;
+impl WaveletType
+{
+    ///centre (angular) frequency of wavelet at scale 1 [rad/frame];
+    ///wavelet at scale `s` corresponds to Fourier frequency `centre_frequency() / (2 * PI * s)` [1/frame]
+    pub fn centre_frequency(self) -> f64
+    {
+        match self
+        {
+            Morlet => MORLET_OMEGA,
+            Shannon | Modified_Shannon | BSpline_2 => SPLINE_OMEGA,
+            //spectrum of Poisson wavelet ~ w^m * exp(-w/sc) peaks at w = m * sc
+            Poisson_1 => POISSON_1.0 as f64 * POISSON_1.1,
+            Poisson_2 => POISSON_2.0 as f64 * POISSON_2.1,
+        }
+    }
+}
 // pub struct WaveletBank(HashMap<WaveletType, WaveletParams>);
 pub type WaveletBank = HashMap<WaveletType, WaveletParams>;
 pub trait WaveletBankTrait
@@ -343,12 +359,19 @@ impl WaveletBankTrait for WaveletBank
     }
 }
 
+///carrier (angular) frequencies of wavelets at scale 1
+const MORLET_OMEGA : f64 = 8.0;
+const SPLINE_OMEGA : f64 = 4.0;
+///(order m, scaling sc) of Poisson wavelets
+const POISSON_1 : (i32, f64) = (1, 4.);
+const POISSON_2 : (i32, f64) = (2, 2.);
+
 fn morlet_wavelet_func(t : &f64, s : &f64) -> (f64, f64)
 {
     let ret = Complex64::new(
         (2.0 / (PI * s).sqrt()) * (-4.0 * (t / s).powi(2)).exp(),
         0.0,
-    ) * Complex64::new(0.0, 8.0 * t / s).exp();
+    ) * Complex64::new(0.0, MORLET_OMEGA * t / s).exp();
     (ret.re, ret.im)
 }
 #[inline(always)]
@@ -357,7 +380,7 @@ fn b_spline_wavelet_func_inner(t : &f64, s : &f64, p : &i32, fb : &f64) -> (f64,
     let fbt = fb * t / s;
     let sinc = if fbt == 0. { 1. } else { fbt.sin() / fbt };
     let ret = Complex64::new((0.5 / (s).sqrt()) * sinc.powi(*p), 0.0)
-        * Complex64::new(0.0, 4.0 * t / s).exp();
+        * Complex64::new(0.0, SPLINE_OMEGA * t / s).exp();
     (ret.re, ret.im)
 }
 
@@ -385,10 +408,10 @@ fn poisson_wavelet_func_inner(t : &f64, s : &f64, m : &i32, sc : &f64) -> (f64,
 
 fn poisson_wavelet_func_1(t : &f64, s : &f64) -> (f64, f64)
 {
-    poisson_wavelet_func_inner(t, s, &1, &4.)
+    poisson_wavelet_func_inner(t, s, &POISSON_1.0, &POISSON_1.1)
 }
 
 fn poisson_wavelet_func_2(t : &f64, s : &f64) -> (f64, f64)
 {
-    poisson_wavelet_func_inner(t, s, &2, &2.)
+    poisson_wavelet_func_inner(t, s, &POISSON_2.0, &POISSON_2.1)
 }